@@ -0,0 +1,167 @@
+use std::cell::Cell;
+
+use crate::p::cnf::{CNF, Literal};
+use crate::solvers::sat::cdcl::IncrementalCDCL;
+pub use crate::solvers::sat::limits::Limits;
+pub use crate::solvers::sat::solver::Error;
+use crate::solvers::sat::solver::{Outcome as SatOutcome, IncrementalSolver};
+
+// Computes the backbone of a satisfiable CNF: the literals true in every model.
+// The first model gives a candidate literal per variable. A candidate l is
+// in the backbone when the formula is unsatisfiable under ¬l; otherwise the
+// new model drops every candidate it falsifies, since their variables take
+// both values. Backbone literals are added as units, which helps later calls.
+#[derive(Clone, Default)]
+pub struct BackboneExtractor {
+    variables: Option<Vec<usize>>,
+    max_calls: Option<u64>,
+    limits: Limits,
+    statistics: Cell<Statistics>,
+}
+
+// Every variable asked about is in exactly one of the three lists.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Backbone {
+    pub literals: Vec<Literal>,  // true in every model
+    pub free: Vec<usize>,  // variables seen with both values
+    pub undecided: Vec<Literal>,  // true in every model found, not proven either way
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    // Exact unless the call budget ran out, then some literals are undecided.
+    Backbone(Backbone),
+    Unsat,
+    // A limit was hit. What was known then, if there was a model.
+    Unknown { reason: String, partial: Option<Backbone> },
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Statistics {
+    pub sat_calls: u64,
+    pub filtered: u64,  // candidates dropped by a model instead of their own call
+}
+
+impl Statistics {
+    pub fn entries(&self) -> Vec<(&'static str, u64)> {
+        vec![
+            ("sat_calls", self.sat_calls),
+            ("filtered", self.filtered),
+        ]
+    }
+}
+
+impl BackboneExtractor {
+    pub fn new() -> BackboneExtractor {
+        BackboneExtractor::default()
+    }
+
+    // Only these variables are examined, all of them by default.
+    pub fn set_variables(&mut self, value: Vec<usize>) {
+        self.variables = Some(value);
+    }
+
+    // Approximate mode: at most this many calls after the first model. The
+    // candidates left are reported as undecided.
+    pub fn set_max_calls(&mut self, value: u64) {
+        self.max_calls = Some(value);
+    }
+
+    // Applied to every solver call, a deadline or cancellation covers the whole computation.
+    pub fn set_limits(&mut self, value: Limits) {
+        self.limits = value;
+    }
+
+    // Statistics of the last computation.
+    pub fn last_statistics(&self) -> Statistics {
+        self.statistics.get()
+    }
+
+    pub fn compute(&self, cnf: &CNF) -> Result<Outcome, Error> {
+        self.compute_with(IncrementalCDCL::new(), cnf)
+    }
+
+    pub fn compute_with<S: IncrementalSolver>(&self, mut solver: S, cnf: &CNF) -> Result<Outcome, Error> {
+        solver.add_cnf(cnf);
+        solver.set_limits(self.limits.clone());
+
+        let variables: Vec<usize> = match &self.variables {
+            Some(variables) => {
+                let mut variables = variables.clone();
+                variables.sort();
+                variables.dedup();
+                variables
+            },
+            None => (1..=cnf.var_count()).collect(),
+        };
+
+        let mut statistics = Statistics::default();
+        let outcome = Self::search(&mut solver, variables, self.max_calls, &mut statistics);
+        self.statistics.set(statistics);
+        outcome
+    }
+
+    fn search<S: IncrementalSolver>(solver: &mut S, variables: Vec<usize>, max_calls: Option<u64>, statistics: &mut Statistics) -> Result<Outcome, Error> {
+        statistics.sat_calls += 1;
+        match solver.solve_with_assumptions(&[])? {
+            SatOutcome::Sat(_) => {},
+            SatOutcome::Unsat => return Ok(Outcome::Unsat),
+            SatOutcome::Unknown(reason) => return Ok(Outcome::Unknown { reason, partial: None }),
+        }
+
+        let mut backbone = Backbone::default();
+        // A variable the model leaves unassigned may take either value.
+        let mut candidates: Vec<Literal> = Vec::new();
+        for v in variables {
+            match solver.value(Literal::new(v, false)) {
+                Some(value) => candidates.push(Literal::new(v, !value)),
+                None => backbone.free.push(v),
+            }
+        }
+
+        let mut calls = 0;
+        while let Some(l) = candidates.pop() {
+            if max_calls.is_some_and(|max| calls >= max) {
+                candidates.push(l);
+                break;
+            }
+            calls += 1;
+
+            statistics.sat_calls += 1;
+            match solver.solve_with_assumptions(&[l.neg()])? {
+                SatOutcome::Unsat => {
+                    solver.add_clause(vec![l]);
+                    backbone.literals.push(l);
+                },
+                SatOutcome::Sat(_) => {
+                    backbone.free.push(l.get_var());
+                    let before = candidates.len();
+                    candidates.retain(|c| {
+                        let kept = solver.value(*c) == Some(true);
+                        if !kept {
+                            backbone.free.push(c.get_var());
+                        }
+                        kept
+                    });
+                    statistics.filtered += (before - candidates.len()) as u64;
+                },
+                SatOutcome::Unknown(reason) => {
+                    candidates.push(l);
+                    backbone.undecided = candidates;
+                    Self::sort(&mut backbone);
+                    return Ok(Outcome::Unknown { reason, partial: Some(backbone) });
+                },
+            }
+        }
+
+        backbone.undecided = candidates;
+        Self::sort(&mut backbone);
+        Ok(Outcome::Backbone(backbone))
+    }
+
+    fn sort(backbone: &mut Backbone) {
+        backbone.literals.sort();
+        backbone.free.sort();
+        backbone.undecided.sort();
+    }
+}
@@ -0,0 +1,171 @@
+use super::mus::{self, Algorithm, Oracle, MusExtractor};
+use crate::p::cnf::{CNF, Literal};
+use crate::solvers::sat::cdcl::IncrementalCDCL;
+pub use crate::solvers::sat::limits::Limits;
+pub use crate::solvers::sat::solver::Error;
+use crate::solvers::sat::solver::{Outcome as SatOutcome, IncrementalSolver};
+
+// Enumerates every minimal unsatisfiable subset (MUS) and every minimal
+// correction set (MCS) of the clauses of a CNF with MARCO (Liffiton,
+// Previti, Malik, Marques-Silva). A map formula over one variable per clause
+// holds the subsets not explored yet. A subset from the map is either
+// satisfiable, then grown to a maximal satisfiable subset whose complement is
+// an MCS, or unsatisfiable, then shrunk to a MUS. The map then blocks every
+// subset of the first and every superset of the second. MUSes and MCSes are
+// minimal hitting sets of each other, so once the map is empty both are complete.
+#[derive(Clone, Default)]
+pub struct Marco {
+    algorithm: Algorithm,
+    limits: Limits,
+}
+
+// Clauses are given by their index in MusExtractor::clauses(cnf).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Subset {
+    Mus(Vec<usize>),  // sorted indices
+    Mcs(Vec<usize>),  // sorted indices, removing these clauses makes the CNF satisfiable
+}
+
+// Lazy iterator over the MUSes and MCSes, each reported once.
+// When a solver gives up, the reason is yielded as an error and the iteration ends.
+pub struct Subsets<S: IncrementalSolver> {
+    oracle: Oracle<S>,
+    map: IncrementalCDCL,
+    clauses: Vec<Vec<Literal>>,
+    algorithm: Algorithm,
+    done: bool,
+}
+
+impl Marco {
+    pub fn new() -> Marco {
+        Marco::default()
+    }
+
+    // How an unsatisfiable subset is shrunk to a MUS.
+    pub fn set_algorithm(&mut self, value: Algorithm) {
+        self.algorithm = value;
+    }
+
+    // Applied to every solver call, a deadline or cancellation covers the whole enumeration.
+    pub fn set_limits(&mut self, value: Limits) {
+        self.limits = value;
+    }
+
+    pub fn subsets(&self, cnf: &CNF) -> Subsets<IncrementalCDCL> {
+        self.subsets_with(IncrementalCDCL::new(), cnf)
+    }
+
+    // The solver tests subsets of clauses, the map always uses IncrementalCDCL.
+    pub fn subsets_with<S: IncrementalSolver>(&self, solver: S, cnf: &CNF) -> Subsets<S> {
+        let clauses = MusExtractor::clauses(cnf);
+        let mut map = IncrementalCDCL::new();
+        map.set_limits(self.limits.clone());
+        Subsets {
+            oracle: Oracle::new(solver, &clauses, self.limits.clone()),
+            map,
+            clauses,
+            algorithm: self.algorithm,
+            done: false,
+        }
+    }
+}
+
+impl<S: IncrementalSolver> Subsets<S> {
+    // Clause i is selected in the map when variable i + 1 is true.
+    fn map_literal(clause: usize) -> Literal {
+        Literal::new(clause + 1, false)
+    }
+
+    // An unexplored subset. Clauses the map leaves open are taken in, which
+    // makes it closer to maximal. None once the map is unsatisfiable.
+    fn seed(&mut self) -> Result<Option<Vec<usize>>, Error> {
+        match self.map.solve_with_assumptions(&[])? {
+            SatOutcome::Sat(_) => {},
+            SatOutcome::Unsat => return Ok(None),
+            SatOutcome::Unknown(reason) => return Err(Error::ResourceExhausted(reason)),
+        }
+        Ok(Some((0..self.clauses.len()).filter(|i| self.map.value(Self::map_literal(*i)) != Some(false)).collect()))
+    }
+
+    // Adds the clauses satisfied by the last model of the oracle.
+    fn absorb(&self, inside: &mut [bool]) {
+        for (i, clause) in self.clauses.iter().enumerate() {
+            if !inside[i] && clause.iter().any(|l| self.oracle.solver().value(*l) == Some(true)) {
+                inside[i] = true;
+            }
+        }
+    }
+
+    // Grows a satisfiable subset, whose model the oracle has just found, to a
+    // maximal one, and returns the clauses left out.
+    fn grow(&mut self, seed: &[usize]) -> Result<Vec<usize>, Error> {
+        let mut inside = vec![false; self.clauses.len()];
+        for i in seed.iter() {
+            inside[*i] = true;
+        }
+        self.absorb(&mut inside);
+
+        for i in 0..self.clauses.len() {
+            if inside[i] {
+                continue;
+            }
+            inside[i] = true;
+            let subset: Vec<usize> = (0..self.clauses.len()).filter(|j| inside[*j]).collect();
+            match self.oracle.check(&subset)? {
+                None => self.absorb(&mut inside),
+                Some(_) => inside[i] = false,
+            }
+        }
+        Ok((0..self.clauses.len()).filter(|i| !inside[*i]).collect())
+    }
+
+    fn explore(&mut self) -> Result<Option<Subset>, Error> {
+        let seed = match self.seed()? {
+            Some(seed) => seed,
+            None => return Ok(None),
+        };
+
+        match self.oracle.check(&seed)? {
+            None => {
+                let mcs = self.grow(&seed)?;
+                // Every subset of the maximal satisfiable subset is explored.
+                self.map.add_clause(mcs.iter().map(|i| Self::map_literal(*i)).collect());
+                Ok(Some(Subset::Mcs(mcs)))
+            },
+            Some(core) => {
+                let mus = mus::shrink(&mut self.oracle, core, self.algorithm)?;
+                // And so is every superset of the MUS.
+                self.map.add_clause(mus.iter().map(|i| Self::map_literal(*i).neg()).collect());
+                Ok(Some(Subset::Mus(mus)))
+            },
+        }
+    }
+}
+
+impl<S: IncrementalSolver> Iterator for Subsets<S> {
+    type Item = Result<Subset, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.explore() {
+            Ok(Some(subset)) => {
+                // An empty MCS or MUS blocks the whole map.
+                if matches!(&subset, Subset::Mus(s) | Subset::Mcs(s) if s.is_empty()) {
+                    self.done = true;
+                }
+                Some(Ok(subset))
+            },
+            Ok(None) => {
+                self.done = true;
+                None
+            },
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            },
+        }
+    }
+}
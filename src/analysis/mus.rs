@@ -0,0 +1,233 @@
+use std::cell::Cell;
+
+use crate::p::cnf::{CNF, Literal};
+use crate::solvers::sat::cdcl::IncrementalCDCL;
+pub use crate::solvers::sat::limits::Limits;
+pub use crate::solvers::sat::solver::Error;
+use crate::solvers::sat::solver::{Outcome as SatOutcome, IncrementalSolver};
+
+// Extracts a minimal unsatisfiable subset (MUS) of the clauses of a CNF:
+// an unsatisfiable subset where dropping any clause makes it satisfiable.
+// Every clause gets a selector literal, and subsets are tested by assuming
+// their selectors. The search starts from the core of the whole formula.
+#[derive(Clone, Default)]
+pub struct MusExtractor {
+    algorithm: Algorithm,
+    limits: Limits,
+    statistics: Cell<Statistics>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Algorithm {
+    // Tries to drop the clauses one by one. When a clause can be dropped, the
+    // core of the remaining clauses drops every clause outside it as well
+    // (clause-set refinement).
+    #[default]
+    Deletion,
+
+    // QuickXplain (Junker): splits the clauses in halves and recurses, which
+    // needs fewer calls when the MUS is small compared to the core.
+    QuickXplain,
+}
+
+// Clauses are given by their index in MusExtractor::clauses(cnf).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Mus(Vec<usize>),  // sorted indices
+    Sat,  // the CNF is satisfiable, there is no MUS
+    // A limit was hit. The smallest unsatisfiable subset found so far, if any.
+    Unknown { reason: String, core: Option<Vec<usize>> },
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Statistics {
+    pub sat_calls: u64,
+    pub refined: u64,  // clauses dropped through cores instead of their own call
+}
+
+impl Statistics {
+    pub fn entries(&self) -> Vec<(&'static str, u64)> {
+        vec![
+            ("sat_calls", self.sat_calls),
+            ("refined", self.refined),
+        ]
+    }
+}
+
+impl MusExtractor {
+    pub fn new() -> MusExtractor {
+        MusExtractor::default()
+    }
+
+    pub fn set_algorithm(&mut self, value: Algorithm) {
+        self.algorithm = value;
+    }
+
+    // Applied to every solver call, a deadline or cancellation covers the whole extraction.
+    pub fn set_limits(&mut self, value: Limits) {
+        self.limits = value;
+    }
+
+    // Statistics of the last extraction.
+    pub fn last_statistics(&self) -> Statistics {
+        self.statistics.get()
+    }
+
+    // The clauses of the CNF in the order indices refer to.
+    pub fn clauses(cnf: &CNF) -> Vec<Vec<Literal>> {
+        cnf.clauses().iter().map(|c| c.to_vec()).collect()
+    }
+
+    pub fn extract(&self, cnf: &CNF) -> Result<Outcome, Error> {
+        self.extract_with(IncrementalCDCL::new(), cnf)
+    }
+
+    pub fn extract_with<S: IncrementalSolver>(&self, solver: S, cnf: &CNF) -> Result<Outcome, Error> {
+        let mut oracle = Oracle::new(solver, &Self::clauses(cnf), self.limits.clone());
+        let all: Vec<usize> = (0..oracle.len()).collect();
+        let outcome = match oracle.check(&all) {
+            Ok(None) => Ok(Outcome::Sat),
+            Ok(Some(core)) => shrink(&mut oracle, core, self.algorithm).map(Outcome::Mus),
+            Err(e) => Err(e),
+        };
+        self.statistics.set(oracle.statistics);
+
+        match outcome {
+            Err(Error::ResourceExhausted(reason)) => Ok(Outcome::Unknown { reason, core: oracle.smallest_core }),
+            outcome => outcome,
+        }
+    }
+
+    // The MUS as clauses, None if the CNF is satisfiable. Hitting a limit is
+    // an Error::ResourceExhausted.
+    pub fn extract_clauses(&self, cnf: &CNF) -> Result<Option<Vec<Vec<Literal>>>, Error> {
+        match self.extract(cnf)? {
+            Outcome::Mus(indices) => {
+                let clauses = Self::clauses(cnf);
+                Ok(Some(indices.into_iter().map(|i| clauses[i].clone()).collect()))
+            },
+            Outcome::Sat => Ok(None),
+            Outcome::Unknown { reason, .. } => Err(Error::ResourceExhausted(reason)),
+        }
+    }
+}
+
+// Reduces an unsatisfiable subset to a MUS.
+pub(crate) fn shrink<S: IncrementalSolver>(oracle: &mut Oracle<S>, core: Vec<usize>, algorithm: Algorithm) -> Result<Vec<usize>, Error> {
+    match algorithm {
+        Algorithm::Deletion => deletion(oracle, core),
+        Algorithm::QuickXplain => {
+            let mut mus = quickxplain(oracle, &mut Vec::new(), false, &core)?;
+            mus.sort();
+            Ok(mus)
+        },
+    }
+}
+
+fn deletion<S: IncrementalSolver>(oracle: &mut Oracle<S>, core: Vec<usize>) -> Result<Vec<usize>, Error> {
+    // Every core of a subset contains the critical clauses: they were needed in a larger one.
+    let mut critical = Vec::new();
+    let mut candidates = core;
+    while let Some(c) = candidates.pop() {
+        let subset: Vec<usize> = critical.iter().chain(candidates.iter()).cloned().collect();
+        match oracle.check(&subset)? {
+            None => critical.push(c),
+            Some(core) => {
+                let before = candidates.len();
+                candidates.retain(|i| core.binary_search(i).is_ok());
+                oracle.statistics.refined += (before - candidates.len()) as u64;
+            },
+        }
+    }
+    critical.sort();
+    Ok(critical)
+}
+
+// A minimal subset X of clauses such that background + X is unsatisfiable,
+// given that background + clauses is. When `added` is set, the background
+// has grown since the caller last knew it to be satisfiable.
+fn quickxplain<S: IncrementalSolver>(oracle: &mut Oracle<S>, background: &mut Vec<usize>, added: bool, clauses: &[usize]) -> Result<Vec<usize>, Error> {
+    if added && oracle.check(background)?.is_some() {
+        return Ok(Vec::new());
+    }
+    if clauses.len() == 1 {
+        return Ok(clauses.to_vec());
+    }
+
+    let (first, second) = clauses.split_at(clauses.len() / 2);
+    let size = background.len();
+
+    background.extend_from_slice(first);
+    let mut second_part = quickxplain(oracle, background, true, second)?;
+    background.truncate(size);
+
+    background.extend_from_slice(&second_part);
+    let first_part = quickxplain(oracle, background, !second_part.is_empty(), first)?;
+    background.truncate(size);
+
+    second_part.extend(first_part);
+    Ok(second_part)
+}
+
+// Tests subsets of clauses: clause i is added as clause ∨ ¬s_i, and a subset
+// is solved under the assumptions s_i.
+pub(crate) struct Oracle<S: IncrementalSolver> {
+    solver: S,
+    first_selector: usize,
+    count: usize,
+    pub(crate) statistics: Statistics,
+    pub(crate) smallest_core: Option<Vec<usize>>,
+}
+
+impl<S: IncrementalSolver> Oracle<S> {
+    pub(crate) fn new(mut solver: S, clauses: &[Vec<Literal>], limits: Limits) -> Oracle<S> {
+        let used = clauses.iter().flatten().map(|l| l.get_var() + 1).max().unwrap_or(1);
+        solver.set_limits(limits);
+        for (i, clause) in clauses.iter().enumerate() {
+            let mut guarded = clause.clone();
+            guarded.push(Literal::new(used + i, true));
+            solver.add_clause(guarded);
+        }
+        Oracle {
+            solver,
+            first_selector: used,
+            count: clauses.len(),
+            statistics: Statistics::default(),
+            smallest_core: None,
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.count
+    }
+
+    pub(crate) fn solver(&self) -> &S {
+        &self.solver
+    }
+
+    pub(crate) fn selector(&self, clause: usize) -> Literal {
+        Literal::new(self.first_selector + clause, false)
+    }
+
+    // None if the subset is satisfiable, otherwise the sorted indices of an
+    // unsatisfiable subset of it. A solver that gives up is an Error::ResourceExhausted.
+    pub(crate) fn check(&mut self, subset: &[usize]) -> Result<Option<Vec<usize>>, Error> {
+        let assumptions: Vec<Literal> = subset.iter().map(|i| self.selector(*i)).collect();
+        self.statistics.sat_calls += 1;
+        match self.solver.solve_with_assumptions(&assumptions)? {
+            SatOutcome::Sat(_) => Ok(None),
+            SatOutcome::Unknown(reason) => Err(Error::ResourceExhausted(reason)),
+            SatOutcome::Unsat => {
+                let mut core: Vec<usize> = self.solver.failed_assumptions().iter()
+                    .map(|l| l.get_var() - self.first_selector)
+                    .collect();
+                core.sort();
+                core.dedup();
+                if self.smallest_core.as_ref().is_none_or(|c| c.len() > core.len()) {
+                    self.smallest_core = Some(core.clone());
+                }
+                Ok(Some(core))
+            },
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use std::fs::File;
+use std::process::ExitCode;
+
+use opt::io;
+use opt::proof::drat;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() != 3 {
+        println!("Usage: {} <cnf> <proof>", args[0]);
+        return ExitCode::FAILURE;
+    }
+
+    let cnf = match io::cnf::get_cnf_from_file(&args[1]) {
+        Ok(cnf) => cnf,
+        Err(err) => {
+            println!("c Error occured while parsing CNF: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let steps = match File::open(&args[2]).and_then(drat::read_proof) {
+        Ok(steps) => steps,
+        Err(err) => {
+            println!("c Error occured while reading proof: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match drat::check(&cnf, &steps) {
+        Ok(()) => {
+            println!("s VERIFIED");
+            ExitCode::SUCCESS
+        },
+        Err(err) => {
+            println!("c {}", err);
+            println!("s NOT VERIFIED");
+            ExitCode::FAILURE
+        }
+    }
+}
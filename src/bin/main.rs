@@ -1,6 +1,6 @@
 fn main() {
     println!("Hello there! Welcome to Opt rust lib!");
     println!("Github: https://github.com/VitaliyEroshin/opt");
-    println!("");
+    println!();
     println!("Check this out for more info")
 }
@@ -0,0 +1,81 @@
+use std::process::ExitCode;
+use std::time::Duration;
+
+use opt::io;
+use opt::solvers::maxsat::linear::LinearSearch;
+use opt::solvers::maxsat::oll::OLL;
+use opt::solvers::maxsat::solver::*;
+
+// Output follows the MaxSAT Evaluation: `o` lines give the cost, the `v` line
+// gives the value of every variable as a string of 0s and 1s. Variables
+// named in the input follow as `c name=value` lines.
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() != 3 && !(args.len() == 5 && args[3] == "--timeout") {
+        println!("Usage: {} <linear|oll> <wcnf> [--timeout SECONDS]", args[0]);
+        return ExitCode::FAILURE;
+    }
+
+    let solver: Box<dyn MaxSatSolver> = match args[1].as_str() {
+        "linear" => Box::new(LinearSearch::new()),
+        "oll" => Box::new(OLL::new()),
+        other => {
+            println!("c Unknown algorithm {}", other);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut limits = Limits::new();
+    if args.len() == 5 {
+        match args[4].parse::<f64>() {
+            Ok(seconds) if seconds.is_finite() && seconds >= 0.0 => limits.set_timeout(Duration::from_secs_f64(seconds)),
+            _ => {
+                println!("c --timeout expects a number of seconds");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    let (wcnf, symbols) = match io::wcnf::get_named_wcnf_from_file(&args[2]) {
+        Ok(named) => named,
+        Err(err) => {
+            println!("c Error occured while parsing WCNF: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let outcome = solver.solve_with_limits(&wcnf, &limits);
+
+    for (name, value) in solver.statistics() {
+        println!("c stat {} {}", name, value);
+    }
+
+    let outcome = match outcome {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            println!("c Error when solving: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if let Some(solution) = outcome.solution() {
+        println!("o {}", solution.cost);
+    }
+    match &outcome {
+        Outcome::Optimum(_) => println!("s OPTIMUM FOUND"),
+        Outcome::Unsat => println!("s UNSATISFIABLE"),
+        Outcome::Unknown { best: Some(_), .. } => println!("s SATISFIABLE"),
+        Outcome::Unknown { best: None, .. } => println!("s UNKNOWN"),
+    }
+    if let Outcome::Unknown { reason, .. } = &outcome {
+        println!("c {}", reason);
+    }
+    if let Some(solution) = outcome.solution() {
+        let values: String = solution.model.literals().map(|l| if l.is_negative() { '0' } else { '1' }).collect();
+        println!("v {}", values);
+        for (name, value) in symbols.named_values(&solution.model) {
+            println!("c {}={}", name, value as u8);
+        }
+    }
+    ExitCode::SUCCESS
+}
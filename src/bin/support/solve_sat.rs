@@ -1,29 +1,104 @@
+use std::process::ExitCode;
+use std::time::Duration;
+
 use opt::{io, p, solvers};
 
 use solvers::sat::solver::*;
 
-pub fn solve_sat<S: Solver>(solver: S) {
-    let cnf = io::cnf::get_cnf_from_stdin();
+const EXIT_SAT: u8 = 10;
+const EXIT_UNSAT: u8 = 20;
+const EXIT_UNKNOWN: u8 = 0;
+const EXIT_ERROR: u8 = 1;
 
-    let c: p::cnf::CNF;
-    match cnf {
-        Ok(cnf) => {
-            c = cnf;
-        },
+pub struct Arguments {
+    pub limits: Limits,
+    pub seed: Option<u64>,
+}
+
+// Reads `--timeout SECONDS` and `--seed N` from the command line, if given.
+pub fn get_arguments() -> Result<Arguments, String> {
+    let mut arguments = Arguments { limits: Limits::new(), seed: None };
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--timeout" => {
+                let seconds: f64 = args.next()
+                    .and_then(|s| s.parse().ok())
+                    .filter(|s: &f64| s.is_finite() && *s >= 0.0)
+                    .ok_or("--timeout expects a number of seconds")?;
+                arguments.limits.set_timeout(Duration::from_secs_f64(seconds));
+            },
+            "--seed" => {
+                let seed = args.next()
+                    .and_then(|s| s.parse().ok())
+                    .ok_or("--seed expects an unsigned integer")?;
+                arguments.seed = Some(seed);
+            },
+            _ => return Err(format!("unknown argument {}", arg)),
+        }
+    }
+
+    Ok(arguments)
+}
+
+// Seed given on the command line; bad arguments are reported by solve_sat.
+#[allow(dead_code)]
+pub fn get_seed() -> Option<u64> {
+    get_arguments().ok().and_then(|arguments| arguments.seed)
+}
+
+pub fn solve_sat<S: Solver>(solver: S) -> ExitCode {
+    let limits = match get_arguments() {
+        Ok(arguments) => arguments.limits,
         Err(err) => {
-            println!("Error occured while parsing CNF: {}", err.to_string());
-            return;
+            println!("c {}", err);
+            return ExitCode::from(EXIT_ERROR);
         }
+    };
+
+    let cnf = io::cnf::get_named_cnf_from_stdin();
+
+    let (c, symbols): (p::cnf::CNF, p::symbols::SymbolTable) = match cnf {
+        Ok(named) => named,
+        Err(err) => {
+            println!("c Error occured while parsing CNF: {}", err);
+            return ExitCode::from(EXIT_ERROR);
+        }
+    };
+
+    let outcome = solver.solve_with_limits(c, &limits);
+
+    for (name, value) in solver.statistics() {
+        println!("c stat {} {}", name, value);
     }
 
-    match solver.solve(c.clone()) {
-        Ok(eval_set) => {
-            for v in eval_set.iter() {
-                print!("{:?} ", v);
+    match outcome {
+        Ok(Outcome::Sat(model)) => {
+            println!("s SATISFIABLE");
+            print!("v");
+            for v in model.literals() {
+                print!(" {:?}", v);
+            }
+            println!(" 0");
+            // Variables named in the input, one `c name=value` line each.
+            for (name, value) in symbols.named_values(&model) {
+                println!("c {}={}", name, value as u8);
             }
+            ExitCode::from(EXIT_SAT)
+        },
+        Ok(Outcome::Unsat) => {
+            println!("s UNSATISFIABLE");
+            ExitCode::from(EXIT_UNSAT)
+        },
+        Ok(Outcome::Unknown(reason)) => {
+            println!("s UNKNOWN");
+            println!("c {}", reason);
+            ExitCode::from(EXIT_UNKNOWN)
         },
         Err(e) => {
-            println!("Error when solving: {:}", e.what());
+            println!("c Error when solving: {}", e);
+            ExitCode::from(EXIT_ERROR)
         }
     }
-}
\ No newline at end of file
+}
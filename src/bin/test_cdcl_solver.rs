@@ -0,0 +1,12 @@
+use std::process::ExitCode;
+
+use opt::solvers::sat::{cdcl};
+
+mod support {
+    pub mod solve_sat;
+}
+
+fn main() -> ExitCode {
+    let solver = cdcl::CDCL::new();
+    support::solve_sat::solve_sat(solver)
+}
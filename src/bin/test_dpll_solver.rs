@@ -1,10 +1,15 @@
+use std::process::ExitCode;
+
 use opt::solvers::sat::{dpll};
 
 mod support {
     pub mod solve_sat;
 }
 
-fn main() {
-    let solver = dpll::DPLL::new();
-    support::solve_sat::solve_sat(solver);
+fn main() -> ExitCode {
+    let mut solver = dpll::DPLL::new();
+    if let Some(seed) = support::solve_sat::get_seed() {
+        solver.set_seed(seed);
+    }
+    support::solve_sat::solve_sat(solver)
 }
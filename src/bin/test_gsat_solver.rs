@@ -0,0 +1,15 @@
+use std::process::ExitCode;
+
+use opt::solvers::sat::{local_search};
+
+mod support {
+    pub mod solve_sat;
+}
+
+fn main() -> ExitCode {
+    let mut solver = local_search::LocalSearch::gsat();
+    if let Some(seed) = support::solve_sat::get_seed() {
+        solver.set_seed(seed);
+    }
+    support::solve_sat::solve_sat(solver)
+}
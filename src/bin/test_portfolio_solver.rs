@@ -0,0 +1,26 @@
+use std::process::ExitCode;
+
+use opt::solvers::sat::{cdcl, dpll, local_search, portfolio, ppsz};
+
+mod support {
+    pub mod solve_sat;
+}
+
+fn main() -> ExitCode {
+    let seed = support::solve_sat::get_seed().unwrap_or(0);
+
+    let mut solver = portfolio::Portfolio::new();
+    solver.add(cdcl::CDCL::new);
+    solver.add(dpll::DPLL::new);
+    solver.add(move || {
+        let mut ppsz = ppsz::PPSZ::new();
+        ppsz.set_seed(seed);
+        ppsz
+    });
+    solver.add(move || {
+        let mut walksat = local_search::LocalSearch::walksat(0.567);
+        walksat.set_seed(seed);
+        walksat
+    });
+    support::solve_sat::solve_sat(solver)
+}
@@ -1,22 +1,23 @@
-use std::io::{self, BufRead};
+use std::io::{self, BufRead, Write};
 use std::fs::File;
 use rand::Rng;
 
-use crate::p::cnf::{CNF, Literal};
+use crate::p::cnf::{CNF, Literal, Var};
+use crate::p::symbols::SymbolTable;
 
-pub fn get_benchmark_cnf(variables: usize, clauses: usize, var_in_clauses: usize) -> CNF {
+// Random CNF drawn from rng, pass a seeded one (e.g. StdRng::seed_from_u64) to get the same CNF every time.
+pub fn get_benchmark_cnf<R: Rng>(variables: usize, clauses: usize, var_in_clauses: usize, rng: &mut R) -> CNF {
     let mut cnf = CNF::new();
     for _ in 0..clauses {
         let mut clause = Vec::new();
         for _ in 0..var_in_clauses {
-            let mut rng = rand::thread_rng();
-            let var = rng.gen_range(0..variables);
+            let var = rng.gen_range(1..=variables);
             let sign = rng.gen();
-            clause.push(Literal { var, sign });
+            clause.push(Literal::new(var, sign));
         }
         cnf.add_clause(clause);
     }
-    return cnf;
+    cnf
 }
 
 pub fn get_cnf_from_file(path: &str) -> Result<CNF, std::io::Error> {
@@ -32,18 +33,81 @@ pub fn get_cnf_from_stdin() -> Result<CNF, std::io::Error> {
     read_cnf_from_buff(&mut reader)
 }
 
+// Same as above, along with the variable names given by the comments.
+pub fn get_named_cnf_from_file(path: &str) -> Result<(CNF, SymbolTable), std::io::Error> {
+    let file = File::open(path)?;
+    let mut reader = io::BufReader::new(file);
+
+    read_named_cnf_from_buff(&mut reader)
+}
+
+pub fn get_named_cnf_from_stdin() -> Result<(CNF, SymbolTable), std::io::Error> {
+    let mut reader = io::BufReader::new(io::stdin().lock());
+
+    read_named_cnf_from_buff(&mut reader)
+}
+
+// Writes the CNF in the format read here, after a `c var <variable> <name>`
+// comment for every named variable. Fails with InvalidInput, writing
+// nothing, if a name is not a single word and would not read back.
+pub fn write_named_cnf<W: Write>(writer: &mut W, cnf: &CNF, symbols: &SymbolTable) -> Result<(), std::io::Error> {
+    if let Some((var, name)) = symbols.iter().find(|(_, name)| !SymbolTable::is_valid_name(name)) {
+        return Err(std::io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("name {:?} of variable {:?} is not a single word", name, var),
+        ));
+    }
+    for (var, name) in symbols.iter() {
+        writeln!(writer, "c var {:?} {}", var, name)?;
+    }
+    writeln!(writer, "{}", cnf)
+}
+
+// Reads a comment, without its leading `c`. Only `c var <variable> <name>`,
+// as in `c var 3 CONFIG_NET`, names the variable. Every other comment is
+// ignored, so ordinary ones never fail or attach names.
+pub(crate) fn read_name_comment(comment: &str, symbols: &mut SymbolTable) -> Result<(), String> {
+    if !comment.starts_with(char::is_whitespace) {
+        return Ok(());
+    }
+    let tokens: Vec<&str> = comment.split_whitespace().collect();
+    let var = match tokens[..] {
+        ["var", var, _] => var.parse::<usize>().ok().filter(|v| (1..=Var::MAX).contains(v)),
+        _ => None,
+    };
+    match var {
+        Some(var) if !symbols.set_name(Var::new(var), tokens[2]) => {
+            Err(format!("name {} given to variable {} clashes with an earlier one", tokens[2], var))
+        },
+        _ => Ok(()),
+    }
+}
+
+// Next line that is not a comment, trimmed; empty at the end of the stream.
+fn read_line_skipping_comments<Stream: std::io::Read>(reader: &mut io::BufReader<Stream>, symbols: &mut SymbolTable) -> Result<String, std::io::Error> {
+    loop {
+        let mut s = String::new();
+        if reader.read_line(&mut s)? == 0 {
+            return Ok(s);
+        }
+        match s.trim().strip_prefix('c') {
+            Some(comment) => read_name_comment(comment, symbols).map_err(std::io::Error::other)?,
+            None => return Ok(s.trim().to_string()),
+        }
+    }
+}
+
 fn parse_clause_from_line(s: &str) -> Result<Vec<Literal>, std::io::Error> {
     let mut clause = Vec::new();
 
     for lit in s.split(" ") {
-        match Literal::new(lit) {
-            Ok(l) => {
+        match Literal::parse(lit) {
+            Some(l) => {
                 clause.push(l);
             },
-            Err(_) => {
-                return Err(std::io::Error::new(
-                    io::ErrorKind::Other,
-                    "Failed to parse literal, must be signed integer"
+            None => {
+                return Err(std::io::Error::other(
+                    "Failed to parse literal, must be nonzero signed integer"
                 ))
             }
         }
@@ -53,20 +117,27 @@ fn parse_clause_from_line(s: &str) -> Result<Vec<Literal>, std::io::Error> {
 }
 
 fn read_cnf_from_buff<Stream: std::io::Read>(reader: &mut io::BufReader<Stream>) -> Result<CNF, std::io::Error> {
-    let mut num_clauses = String::new();
-    reader.read_line(&mut num_clauses)?;
+    read_named_cnf_from_buff(reader).map(|(cnf, _)| cnf)
+}
+
+// Lines starting with `c` are comments, anywhere in the stream. The symbol
+// table has every variable of the CNF taken, so names and fresh variables
+// added later do not collide with them.
+pub fn read_named_cnf_from_buff<Stream: std::io::Read>(reader: &mut io::BufReader<Stream>) -> Result<(CNF, SymbolTable), std::io::Error> {
+    let mut symbols = SymbolTable::new();
+    let num_clauses = read_line_skipping_comments(reader, &mut symbols)?;
 
-    let num_clauses: i32 = num_clauses.trim().parse().unwrap_or(0);
+    let num_clauses: i32 = num_clauses.parse().unwrap_or(0);
 
     let mut cnf = CNF::new();
 
     for _ in 1..=num_clauses {
-        let mut s = String::new();
-        reader.read_line(&mut s)?;
+        let s = read_line_skipping_comments(reader, &mut symbols)?;
 
-        cnf.add_clause(parse_clause_from_line(s.trim())?);
+        cnf.add_clause(parse_clause_from_line(&s)?);
 
     }
 
-    Ok(cnf)
+    symbols.reserve(cnf.var_count());
+    Ok((cnf, symbols))
 }
\ No newline at end of file
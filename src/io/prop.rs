@@ -1,6 +1,7 @@
 use std::fmt::{Debug};
 
-use crate::p::cnf::{CNF, Literal};
+use crate::p::cnf::{CNF, Var};
+use crate::p::symbols::SymbolTable;
 
 pub struct PropositionalFormula {
     formula: String,
@@ -11,7 +12,7 @@ pub struct PropositionalFormula {
 struct ComputationTree {
     operation: String,
     children: Vec<ComputationTree>,
-    value: Option<i32>,
+    value: Option<String>,  // leaf: a variable index or name
 }
 
 impl Debug for ComputationTree {
@@ -23,14 +24,14 @@ impl Debug for ComputationTree {
 impl ComputationTree {
     fn fmt_with_indent(&self, f: &mut std::fmt::Formatter<'_>, indent: usize) -> std::fmt::Result {
         let str_indent = " ".repeat(indent);
-        if self.value.is_some() {
-            return write!(f, "{}- {}\n", str_indent, self.value.unwrap())
+        if let Some(value) = &self.value {
+            return writeln!(f, "{}- {}", str_indent, value)
         } 
-        write!(f, "{}{}: [\n", str_indent, self.operation)?;
+        writeln!(f, "{}{}: [", str_indent, self.operation)?;
         for child in &self.children {
             child.fmt_with_indent(f, indent + 2)?;
         }
-        write!(f, "{}],\n", str_indent)
+        writeln!(f, "{}],", str_indent)
     }
 }
 
@@ -47,12 +48,12 @@ impl PropositionalFormula {
 
     pub fn new(formula: String) -> PropositionalFormula {
         PropositionalFormula {
-            formula: formula,
+            formula,
             tree: None,
         }
     }
 
-    fn make_variable_node(var: i32) -> ComputationTree {
+    fn make_variable_node(var: String) -> ComputationTree {
         ComputationTree {
             operation: String::new(),
             children: Vec::new(),
@@ -74,26 +75,16 @@ impl PropositionalFormula {
     }
 
     fn is_binary_operation(&self, operation: &str) -> bool {
-        for op in PropositionalFormula::BINARY_OPERATIONS.iter() {
-            if op.contains(&operation) {
-                return true;
-            }
-        }
-        return false;
+        PropositionalFormula::BINARY_OPERATIONS.contains(&operation)
     }
 
     fn is_unary_operation(&self, operation: &str) -> bool {
-        for op in PropositionalFormula::UNARY_OPERATIONS.iter() {
-            if op.contains(&operation) {
-                return true;
-            }
-        }
-        return false;
+        PropositionalFormula::UNARY_OPERATIONS.contains(&operation)
     }
 
     fn make_binary_operation_node(operation: String, left: ComputationTree, right: ComputationTree) -> ComputationTree {
         ComputationTree {
-            operation: operation,
+            operation,
             children: vec![left, right],
             value: None,
         }
@@ -101,7 +92,7 @@ impl PropositionalFormula {
 
     fn make_unary_operation_node(operation: String, child: ComputationTree) -> ComputationTree {
         ComputationTree {
-            operation: operation,
+            operation,
             children: vec![child],
             value: None,
         }
@@ -114,20 +105,20 @@ impl PropositionalFormula {
         for c in self.formula.chars() {
             match c {
                 ' ' => {
-                    if token.len() > 0 {
+                    if !token.is_empty() {
                         tokens.push(token);
                         token = String::new();
                     }
                 }
                 '(' => {
-                    if token.len() > 0 {
+                    if !token.is_empty() {
                         tokens.push(token);
                         token = String::new();
                     }
                     tokens.push(String::from("("));
                 }
                 ')' => {
-                    if token.len() > 0 {
+                    if !token.is_empty() {
                         tokens.push(token);
                         token = String::new();
                     }
@@ -138,7 +129,7 @@ impl PropositionalFormula {
                 }
             }
         }
-        if token.len() > 0 {
+        if !token.is_empty() {
             tokens.push(token);
         }
         tokens
@@ -148,9 +139,9 @@ impl PropositionalFormula {
         let mut children = Vec::new();
         for child in tr.children.iter() {
             if child.operation != operation {
-                children.push(self.reduce_tree_height(&child));
+                children.push(self.reduce_tree_height(child));
             } else {
-                children.append(&mut self.get_other_op_children(&child, operation));
+                children.append(&mut self.get_other_op_children(child, operation));
             }
         }
         children
@@ -163,7 +154,7 @@ impl PropositionalFormula {
         let operation = tr.operation.clone();
         ComputationTree { 
             operation: operation.clone(), 
-            children: self.get_other_op_children(&tr, &operation), 
+            children: self.get_other_op_children(tr, &operation), 
             value: None 
         }
     }
@@ -180,7 +171,7 @@ impl PropositionalFormula {
                 while !found_opening_parenthesis {
                     let first = stack.last().unwrap().clone();
                     stack.pop();
-                    if stack.last().unwrap().operation == "(".to_string() {
+                    if stack.last().unwrap().operation == "(" {
                         stack.pop();
                         stack.push(first);
                         break;
@@ -193,7 +184,7 @@ impl PropositionalFormula {
                     } else {
                         let second = stack.last().unwrap().clone();
                         stack.pop();
-                        if stack.last().unwrap().operation == "(".to_string() {
+                        if stack.last().unwrap().operation == "(" {
                             found_opening_parenthesis = true;
                             stack.pop();
                         }
@@ -206,9 +197,9 @@ impl PropositionalFormula {
                 }
             } else if self.is_binary_operation(&token) || self.is_unary_operation(&token) {
                 stack.push(Self::make_service_node("_"));
-                operations.push(token);
+                operations.push(Self::get_operation_from_alias(&token).to_string());
             } else {
-                stack.push(Self::make_variable_node(token.parse::<i32>().unwrap()));
+                stack.push(Self::make_variable_node(token));
             }
         }
         stack.pop().unwrap()
@@ -223,44 +214,54 @@ impl PropositionalFormula {
         self.tree = Some(tr);
     }
 
-    pub fn get_cnf(&mut self) -> Option<CNF> {
-        if self.tree.is_none() {
+    // Clauses of the formula, a conjunction of disjunctions of variables and
+    // negated variables. A variable is its index, or a name looked up in the
+    // symbol table and given a fresh variable there on first use. Indices are
+    // taken before any name, so names never land on them.
+    pub fn get_cnf(&self, symbols: &mut SymbolTable) -> Option<CNF> {
+        let tree = self.tree.as_ref()?;
+        if tree.operation != "and" {
             return None;
         }
 
-        let mut cnf = CNF::new();
-        if self.tree.as_ref().unwrap().operation != "and" {
-            return None;
-        }
-
-        for child in self.tree.as_ref().unwrap().children.iter() {
+        let mut clauses = Vec::new();
+        for child in tree.children.iter() {
             if child.operation != "or" {
                 return None;
             }
             let mut clause = Vec::new();
             for grandchild in child.children.iter() {
                 if grandchild.operation == "not" {
-                    if grandchild.children.len() != 1 || grandchild.children[0].value.is_none() {
+                    if grandchild.children.len() != 1 {
                         return None;
                     }
-
-                    clause.push(Literal {
-                        var: grandchild.children[0].value.unwrap() as usize,
-                        sign: true,
-                    });
+                    clause.push((grandchild.children[0].value.as_deref()?, true));
                 } else {
-                    if grandchild.value.is_none() {
-                        return None;
-                    }
+                    clause.push((grandchild.value.as_deref()?, false));
+                }
+            }
+            clauses.push(clause);
+        }
 
-                    clause.push(Literal { 
-                        var: grandchild.value.unwrap() as usize, 
-                        sign: false,
-                    });
+        for (leaf, _) in clauses.iter().flatten() {
+            if let Ok(var) = leaf.parse::<i64>() {
+                if var < 1 || var as u64 > Var::MAX as u64 {
+                    return None;
                 }
+                symbols.reserve(var as usize);
             }
-            cnf.add_clause(clause);
+        }
+
+        let mut cnf = CNF::new();
+        for clause in clauses {
+            cnf.add_clause(clause.into_iter().map(|(leaf, negative)| {
+                let var = match leaf.parse::<usize>() {
+                    Ok(var) => Var::new(var),
+                    Err(_) => symbols.var(leaf),
+                };
+                var.literal(negative)
+            }).collect());
         }
         Some(cnf)
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,106 @@
+use std::io::{self, BufRead};
+use std::fs::File;
+
+use super::cnf::read_name_comment;
+use crate::p::cnf::Literal;
+use crate::p::symbols::SymbolTable;
+use crate::p::wcnf::WCNF;
+
+// Reads the WCNF formats of the MaxSAT Evaluations:
+//  - since 2022, `h 1 -2 0` is a hard clause and `5 1 -2 0` a soft clause of weight 5;
+//  - before, a `p wcnf <variables> <clauses> <top>` header comes first, every
+//    clause starts with its weight and the ones weighing at least top are hard.
+//    Without top, all the clauses are soft.
+// Lines starting with `c` are comments, `c var <variable> <name>` naming the
+// variable as in io::cnf.
+pub fn get_wcnf_from_file(path: &str) -> Result<WCNF, std::io::Error> {
+    let file = File::open(path)?;
+    let mut reader = io::BufReader::new(file);
+
+    read_wcnf_from_buff(&mut reader)
+}
+
+pub fn get_wcnf_from_stdin() -> Result<WCNF, std::io::Error> {
+    let mut reader = io::BufReader::new(io::stdin().lock());
+
+    read_wcnf_from_buff(&mut reader)
+}
+
+pub fn get_named_wcnf_from_file(path: &str) -> Result<(WCNF, SymbolTable), std::io::Error> {
+    let file = File::open(path)?;
+    let mut reader = io::BufReader::new(file);
+
+    read_named_wcnf_from_buff(&mut reader)
+}
+
+fn parse_error(line: usize, what: &str) -> std::io::Error {
+    std::io::Error::other(format!("line {}: {}", line, what))
+}
+
+pub fn read_wcnf_from_buff<Stream: std::io::Read>(reader: &mut io::BufReader<Stream>) -> Result<WCNF, std::io::Error> {
+    read_named_wcnf_from_buff(reader).map(|(wcnf, _)| wcnf)
+}
+
+pub fn read_named_wcnf_from_buff<Stream: std::io::Read>(reader: &mut io::BufReader<Stream>) -> Result<(WCNF, SymbolTable), std::io::Error> {
+    let mut wcnf = WCNF::new();
+    let mut symbols = SymbolTable::new();
+    let mut top: Option<u64> = None;
+
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        let number = number + 1;
+        let mut tokens = line.split_whitespace().peekable();
+
+        match tokens.peek() {
+            None => continue,
+            Some(token) if token.starts_with('c') => {
+                read_name_comment(&line.trim()[1..], &mut symbols).map_err(|e| parse_error(number, &e))?;
+                continue;
+            },
+            Some(&"p") => {
+                let fields: Vec<&str> = tokens.collect();
+                if fields.len() < 4 || fields[1] != "wcnf" {
+                    return Err(parse_error(number, "expected p wcnf <variables> <clauses> [<top>]"));
+                }
+                top = match fields.get(4) {
+                    Some(top) => Some(top.parse().map_err(|_| parse_error(number, "top must be an unsigned integer"))?),
+                    None => None,
+                };
+                continue;
+            },
+            _ => {},
+        }
+
+        let hard_mark = tokens.peek() == Some(&"h");
+        let weight = if hard_mark {
+            tokens.next();
+            None
+        } else {
+            let weight: u64 = tokens.next().unwrap().parse()
+                .map_err(|_| parse_error(number, "weight must be an unsigned integer"))?;
+            Some(weight)
+        };
+
+        let mut clause = Vec::new();
+        let mut terminated = false;
+        for token in tokens {
+            if token == "0" {
+                terminated = true;
+                break;
+            }
+            clause.push(Literal::parse(token).ok_or_else(|| parse_error(number, "literal must be a nonzero signed integer"))?);
+        }
+        if !terminated {
+            return Err(parse_error(number, "clause must end with 0"));
+        }
+
+        match weight {
+            None => wcnf.add_hard(clause),
+            Some(weight) if top.is_some_and(|top| weight >= top) => wcnf.add_hard(clause),
+            Some(weight) => wcnf.add_soft(clause, weight),
+        }
+    }
+
+    symbols.reserve(wcnf.var_count());
+    Ok((wcnf, symbols))
+}
@@ -1,15 +1,67 @@
 pub mod p {
+    pub mod assignment;
+    pub mod bigint;
     pub mod cnf;
+    pub mod collections;
+    pub mod symbols;
+    pub mod wcnf;
 }
 
 pub mod io {
     pub mod cnf;
+    pub mod prop;
+    pub mod wcnf;
+}
+
+pub mod analysis {
+    pub mod backbone;
+    pub mod marco;
+    pub mod mus;
+}
+
+pub mod preprocess {
+    pub(crate) mod blocked;
+    pub(crate) mod elimination;
+    pub(crate) mod formula;
+    pub mod preprocessor;
+    pub(crate) mod probing;
+    pub mod reconstruction;
+    pub(crate) mod subsumption;
+}
+
+pub mod proof {
+    pub mod drat;
 }
 
 pub mod solvers {
+    pub mod counting {
+        pub mod approx;
+        pub(crate) mod cell;
+        pub mod exact;
+        pub mod xor;
+    }
+
+    pub mod maxsat {
+        pub mod adder;
+        pub mod linear;
+        pub mod oll;
+        pub mod solver;
+        pub mod totalizer;
+    }
+
+    pub mod sampling {
+        pub mod sampler;
+    }
+
     pub mod sat {
+        pub mod allsat;
+        pub mod branching;
+        pub mod cdcl;
         pub mod ppsz;
         pub mod dpll;
+        pub mod limits;
+        pub mod local_search;
+        pub mod portfolio;
         pub mod solver;
     }
 }
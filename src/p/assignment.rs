@@ -0,0 +1,152 @@
+use super::cnf::Literal;
+use std::fmt::{Debug};
+
+// A partial assignment of the variables 1..=var_count: each one is true,
+// false or unassigned. Assigning a variable beyond var_count extends the
+// range.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Assignment {
+    values: Vec<Option<bool>>,  // by variable, index 0 unused
+}
+
+// Three-valued result of evaluating a clause or a CNF under an assignment.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Evaluation {
+    Satisfied,
+    Falsified,
+    Undetermined,  // some clause neither satisfied nor falsified yet
+}
+
+// The literal could not be assigned: its negation already is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Contradiction(pub Literal);
+
+impl std::fmt::Display for Contradiction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} contradicts {:?}", self.0, self.0.neg())
+    }
+}
+
+impl std::error::Error for Contradiction {}
+
+impl Debug for Assignment {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_list().entries(self.literals()).finish()
+    }
+}
+
+impl Default for Assignment {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl Evaluation {
+    pub fn is_satisfied(&self) -> bool {
+        matches!(self, Evaluation::Satisfied)
+    }
+
+    pub fn is_falsified(&self) -> bool {
+        matches!(self, Evaluation::Falsified)
+    }
+}
+
+impl Assignment {
+    // Leaves every variable of 1..=var_count unassigned.
+    pub fn new(var_count: usize) -> Assignment {
+        Assignment {
+            values: vec![None; var_count + 1],
+        }
+    }
+
+    // Values indexed by variable. Index 0 stands for no variable and is ignored.
+    pub fn from_values(mut values: Vec<Option<bool>>) -> Assignment {
+        match values.first_mut() {
+            Some(value) => *value = None,
+            None => values.push(None),
+        }
+        Assignment { values }
+    }
+
+    // Assigns the literals in turn, over the variables up to the largest one.
+    pub fn from_literals(literals: &[Literal]) -> Result<Assignment, Contradiction> {
+        let mut assignment = Assignment::new(literals.iter().map(|l| l.get_var()).max().unwrap_or(0));
+        for l in literals.iter() {
+            assignment.assign(*l)?;
+        }
+        Ok(assignment)
+    }
+
+    pub fn var_count(&self) -> usize {
+        self.values.len() - 1
+    }
+
+    // Number of assigned variables.
+    pub fn len(&self) -> usize {
+        self.values.iter().filter(|v| v.is_some()).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.iter().all(|v| v.is_none())
+    }
+
+    // Value of the literal, None if its variable is unassigned.
+    pub fn value(&self, l: Literal) -> Option<bool> {
+        self.values.get(l.get_var()).cloned().flatten().map(|v| v != l.is_negative())
+    }
+
+    // Makes the literal true. Fails, leaving the assignment unchanged, if it
+    // is already false.
+    pub fn assign(&mut self, l: Literal) -> Result<(), Contradiction> {
+        match self.value(l) {
+            Some(false) => Err(Contradiction(l)),
+            Some(true) => Ok(()),
+            None => {
+                if self.values.len() <= l.get_var() {
+                    self.values.resize(l.get_var() + 1, None);
+                }
+                self.values[l.get_var()] = Some(!l.is_negative());
+                Ok(())
+            },
+        }
+    }
+
+    pub fn unassign(&mut self, var: usize) {
+        if let Some(value) = self.values.get_mut(var) {
+            *value = None;
+        }
+    }
+
+    pub fn is_assigned(&self, var: usize) -> bool {
+        self.values.get(var).is_some_and(|v| v.is_some())
+    }
+
+    // Variables of 1..=var_count left unassigned, in increasing order.
+    pub fn unassigned(&self) -> impl Iterator<Item = usize> + '_ {
+        (1..self.values.len()).filter(|v| self.values[*v].is_none())
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.unassigned().next().is_none()
+    }
+
+    // The true literals, one per assigned variable, ordered by variable.
+    pub fn literals(&self) -> impl Iterator<Item = Literal> + '_ {
+        self.values.iter()
+            .enumerate()
+            .skip(1)
+            .filter_map(|(var, value)| value.map(|v| Literal::new(var, !v)))
+    }
+
+    pub fn eval_clause(&self, clause: &[Literal]) -> Evaluation {
+        let mut undetermined = false;
+        for l in clause.iter() {
+            match self.value(*l) {
+                Some(true) => return Evaluation::Satisfied,
+                Some(false) => {},
+                None => undetermined = true,
+            }
+        }
+        if undetermined { Evaluation::Undetermined } else { Evaluation::Falsified }
+    }
+}
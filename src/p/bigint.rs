@@ -0,0 +1,177 @@
+use std::cmp::Ordering;
+use std::fmt::{Debug, Display};
+use std::ops::{Add, Mul, Shl};
+
+// Arbitrary precision unsigned integer, enough for model counts:
+// addition, multiplication, powers of two and decimal output.
+// Limbs are little-endian base 2^32 with no leading zero limbs.
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct BigUint {
+    limbs: Vec<u32>,
+}
+
+impl BigUint {
+    pub fn zero() -> BigUint {
+        BigUint { limbs: Vec::new() }
+    }
+
+    pub fn one() -> BigUint {
+        BigUint::from(1)
+    }
+
+    // 2^k
+    pub fn pow2(k: usize) -> BigUint {
+        &BigUint::one() << k
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    // Number of bits needed to write the value, 0 for zero.
+    pub fn bits(&self) -> usize {
+        match self.limbs.last() {
+            Some(top) => 32 * self.limbs.len() - top.leading_zeros() as usize,
+            None => 0,
+        }
+    }
+
+    pub fn to_u64(&self) -> Option<u64> {
+        match self.limbs.len() {
+            0 => Some(0),
+            1 => Some(self.limbs[0] as u64),
+            2 => Some(self.limbs[0] as u64 | (self.limbs[1] as u64) << 32),
+            _ => None,
+        }
+    }
+
+    // Nearest f64, infinity if the value is too large.
+    pub fn to_f64(&self) -> f64 {
+        self.limbs.iter().rev().fold(0.0, |acc, limb| acc * 4294967296.0 + *limb as f64)
+    }
+
+    fn normalize(mut self) -> BigUint {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+        self
+    }
+
+    // Divides in place by a small divisor, returning the remainder.
+    fn div_rem_small(&mut self, divisor: u32) -> u32 {
+        let mut rem = 0u64;
+        for limb in self.limbs.iter_mut().rev() {
+            let cur = rem << 32 | *limb as u64;
+            *limb = (cur / divisor as u64) as u32;
+            rem = cur % divisor as u64;
+        }
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+        rem as u32
+    }
+}
+
+impl From<u64> for BigUint {
+    fn from(value: u64) -> BigUint {
+        BigUint { limbs: vec![value as u32, (value >> 32) as u32] }.normalize()
+    }
+}
+
+impl Add<&BigUint> for &BigUint {
+    type Output = BigUint;
+
+    fn add(self, other: &BigUint) -> BigUint {
+        let n = self.limbs.len().max(other.limbs.len());
+        let mut limbs = Vec::with_capacity(n + 1);
+        let mut carry = 0u64;
+        for i in 0..n {
+            let sum = *self.limbs.get(i).unwrap_or(&0) as u64 + *other.limbs.get(i).unwrap_or(&0) as u64 + carry;
+            limbs.push(sum as u32);
+            carry = sum >> 32;
+        }
+        limbs.push(carry as u32);
+        BigUint { limbs }.normalize()
+    }
+}
+
+impl Mul<&BigUint> for &BigUint {
+    type Output = BigUint;
+
+    fn mul(self, other: &BigUint) -> BigUint {
+        if self.is_zero() || other.is_zero() {
+            return BigUint::zero();
+        }
+
+        let mut limbs = vec![0u32; self.limbs.len() + other.limbs.len()];
+        for (i, a) in self.limbs.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, b) in other.limbs.iter().enumerate() {
+                let cur = limbs[i + j] as u64 + *a as u64 * *b as u64 + carry;
+                limbs[i + j] = cur as u32;
+                carry = cur >> 32;
+            }
+            limbs[i + other.limbs.len()] = carry as u32;
+        }
+        BigUint { limbs }.normalize()
+    }
+}
+
+impl Shl<usize> for &BigUint {
+    type Output = BigUint;
+
+    fn shl(self, k: usize) -> BigUint {
+        if self.is_zero() {
+            return BigUint::zero();
+        }
+
+        let (words, bits) = (k / 32, k % 32);
+        let mut limbs = vec![0u32; words];
+        let mut carry = 0u32;
+        for limb in self.limbs.iter() {
+            limbs.push(limb << bits | carry);
+            carry = if bits == 0 { 0 } else { limb >> (32 - bits) };
+        }
+        limbs.push(carry);
+        BigUint { limbs }.normalize()
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &BigUint) -> Ordering {
+        self.limbs.len().cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &BigUint) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Display for BigUint {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+
+        let mut rest = self.clone();
+        let mut chunks = Vec::new();
+        while !rest.is_zero() {
+            chunks.push(rest.div_rem_small(1_000_000_000));
+        }
+
+        write!(f, "{}", chunks.last().unwrap())?;
+        for chunk in chunks.iter().rev().skip(1) {
+            write!(f, "{:09}", chunk)?;
+        }
+        Ok(())
+    }
+}
+
+impl Debug for BigUint {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self)
+    }
+}
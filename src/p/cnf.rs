@@ -1,58 +1,152 @@
-use std::{collections::HashSet, hash::Hash};
+use std::collections::hash_map::Entry;
+use std::hash::{BuildHasher, Hash};
+
+use super::assignment::{Assignment, Evaluation};
+use super::collections::{FixedState, HashMap};
 use std::fmt::{Debug};
 
+// A variable. Variables are numbered from 1, as in DIMACS.
 #[derive(Eq, Hash, PartialEq, Clone, Copy, PartialOrd, Ord)]
-pub struct Literal {
-    pub var: usize,
-    pub sign: bool,
-}
+pub struct Var(u32);
+
+// A literal packed as 2 * var + sign, the sign bit being set for negative
+// literals. Negation flips that bit, and the code indexes per-literal arrays
+// directly. Literals sort by variable, the positive one first.
+#[derive(Eq, Hash, PartialEq, Clone, Copy, PartialOrd, Ord)]
+pub struct Literal(u32);
 
+// Identifies a clause of a CNF. Ids are given in the order clauses are
+// added, stay valid until the clause is removed and are never reused.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ClauseId(usize);
+
+// The clauses live back to back in one arena of literals, a clause being a
+// span of it. Removed clauses keep their span, so ids stay stable, and
+// everything walks the clauses in the order of their ids.
 #[derive(Clone)]
 pub struct CNF {
-    clauses: HashSet<Vec<Literal>>,
+    literals: Vec<Literal>,
+    spans: Vec<(usize, usize)>,  // start and length of every clause, by id
+    removed: Vec<bool>,
+    occurrences: Vec<Vec<ClauseId>>,  // live clauses containing each literal, by increasing id
+    duplicates: Option<Duplicates>,
+    live: usize,
     variables: usize,
 }
 
+// Index of the live clauses by hash, for duplicate detection. A clause whose
+// hash is already taken by a different one goes into the short list of
+// collisions.
+#[derive(Clone, Default)]
+struct Duplicates {
+    first: HashMap<u64, ClauseId>,
+    collisions: Vec<(u64, ClauseId)>,
+}
+
+// Clauses of a CNF that an assignment does not satisfy, by increasing id.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Diagnostics {
+    pub falsified: Vec<ClauseId>,
+    pub unresolved: Vec<ClauseId>,  // neither satisfied nor falsified
+}
+
+// A view over the live clauses of a CNF.
+#[derive(Clone, Copy)]
+pub struct Clauses<'a> {
+    cnf: &'a CNF,
+}
+
+impl Debug for Var {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 impl Debug for Literal {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{}", if self.sign { "-" } else { "" })?;
-        write!(f, "{}", self.var)
+        write!(f, "{}", if self.is_negative() { "-" } else { "" })?;
+        write!(f, "{}", self.get_var())
+    }
+}
+
+impl Var {
+    // Largest variable a literal can hold.
+    pub const MAX: usize = (u32::MAX >> 1) as usize;
+
+    // Panics unless 1 <= index <= Var::MAX.
+    pub fn new(index: usize) -> Var {
+        assert!(index != 0 && index <= Var::MAX, "variable {} out of range 1..={}", index, Var::MAX);
+        Var(index as u32)
+    }
+
+    pub fn index(&self) -> usize {
+        self.0 as usize
+    }
+
+    pub fn literal(&self, negative: bool) -> Literal {
+        Literal(2 * self.0 + negative as u32)
+    }
+
+    pub fn positive(&self) -> Literal {
+        self.literal(false)
+    }
+
+    pub fn negative(&self) -> Literal {
+        self.literal(true)
     }
 }
 
 impl Literal {
+    // Literal of the variable, negative when sign is set. Panics unless 1 <= var <= Var::MAX.
+    pub fn new(var: usize, sign: bool) -> Literal {
+        Var::new(var).literal(sign)
+    }
+
+    // The DIMACS literal i. Panics on 0, which ends clauses in DIMACS and is
+    // no literal, and on i32::MIN, whose variable is above Var::MAX.
     pub fn from_int(i: i32) -> Literal {
-        Literal {
-            var: i.abs() as usize,
-            sign: i < 0
+        assert!(i != 0, "0 is not a literal");
+        assert!(i.unsigned_abs() as usize <= Var::MAX, "literal {} out of range, variables go up to {}", i, Var::MAX);
+        Literal::new(i.unsigned_abs() as usize, i < 0)
+    }
+
+    // Parses a DIMACS literal. None unless s is a nonzero integer whose
+    // variable is at most Var::MAX.
+    pub fn parse(s: &str) -> Option<Literal> {
+        match s.parse::<i32>() {
+            Ok(i) if i != 0 && i.unsigned_abs() as usize <= Var::MAX => Some(Literal::from_int(i)),
+            _ => None,
         }
     }
 
-    pub fn new(s: &str) -> Result<Literal, std::num::ParseIntError> {
-        let i = s.parse::<i32>()?;
-        Ok(Literal {
-            var: i.abs() as usize,
-            sign: i < 0,
-        })
+    // The literal with the given code 2 * var + sign. Panics if its variable is 0.
+    pub fn from_index(index: usize) -> Literal {
+        Literal::new(index / 2, index % 2 == 1)
+    }
+
+    // The code 2 * var + sign, an index into arrays holding two entries per variable.
+    pub fn index(&self) -> usize {
+        self.0 as usize
     }
 
     pub fn neg(&self) -> Literal {
-        return Literal {
-            var: self.var,
-            sign: !self.sign,
-        };
+        Literal(self.0 ^ 1)
+    }
+
+    pub fn var(&self) -> Var {
+        Var(self.0 >> 1)
     }
 
     pub fn get_var(&self) -> usize {
-        return self.var;
+        (self.0 >> 1) as usize
     }
 
     pub fn is_negative(&self) -> bool {
-        return self.sign;
+        self.0 & 1 == 1
     }
 }
 
-fn print_vec_with_separator(literals: &Vec<Literal>, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+fn print_vec_with_separator(literals: &[Literal], f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     let mut iter = literals.iter();
 
     match iter.next() {
@@ -71,8 +165,8 @@ fn print_vec_with_separator(literals: &Vec<Literal>, f: &mut std::fmt::Formatter
 
 impl std::fmt::Display for CNF {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}\n", self.clauses.len())?;
-        let mut iter = self.clauses.iter();
+        writeln!(f, "{}", self.live)?;
+        let mut iter = self.iter();
 
         match iter.next() {
             None => {}
@@ -82,60 +176,247 @@ impl std::fmt::Display for CNF {
         }
 
         for clause in iter {
-            write!(f, "\n")?;
+            writeln!(f)?;
             print_vec_with_separator(clause, f)?;
         }
         Ok(())
     }
 }
 
+impl Default for CNF {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ClauseId {
+    pub fn index(&self) -> usize {
+        self.0
+    }
+}
+
 impl CNF {
+    // Duplicate clauses are detected and dropped by default.
     pub fn new() -> CNF {
         CNF {
-            clauses: HashSet::new(),
+            literals: Vec::new(),
+            spans: Vec::new(),
+            removed: Vec::new(),
+            occurrences: Vec::new(),
+            duplicates: Some(Duplicates::default()),
+            live: 0,
             variables: 0,
         }
     }
 
+    // Without detection, adding a clause costs no hashing, and a clause added
+    // twice is kept twice.
+    pub fn set_detect_duplicates(&mut self, value: bool) {
+        if !value {
+            self.duplicates = None;
+        } else if self.duplicates.is_none() {
+            let mut duplicates = Duplicates::default();
+            for id in self.ids().collect::<Vec<ClauseId>>() {
+                duplicates.insert(Self::hash(self.clause(id)), id);
+            }
+            self.duplicates = Some(duplicates);
+        }
+    }
+
+    // Largest variable index ever added, removed clauses included.
     pub fn var_count(&self) -> usize {
-        return self.variables;
+        self.variables
+    }
+
+    // Number of live clauses.
+    pub fn len(&self) -> usize {
+        self.live
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.live == 0
     }
 
-    pub fn add_clause(&mut self, mut clause: Vec<Literal>) {
+    // Sorts the literals and drops repeated ones. Returns the id of the new
+    // clause, None if it is a duplicate (when detection is on).
+    pub fn add_clause(&mut self, mut clause: Vec<Literal>) -> Option<ClauseId> {
         clause.sort();
         clause.dedup();
-        for v in clause.iter() {
-            self.variables = std::cmp::max(self.variables, v.get_var())
+
+        let hash = Self::hash(&clause);
+        if let Some(duplicates) = &self.duplicates {
+            if duplicates.find(hash, |id| self.clause(id) == clause.as_slice()).is_some() {
+                return None;
+            }
+        }
+
+        let id = ClauseId(self.spans.len());
+        for l in clause.iter() {
+            self.variables = std::cmp::max(self.variables, l.get_var());
+            let i = l.index();
+            if self.occurrences.len() <= i {
+                self.occurrences.resize(i + 1, Vec::new());
+            }
+            self.occurrences[i].push(id);
+        }
+        if let Some(duplicates) = &mut self.duplicates {
+            duplicates.insert(hash, id);
         }
-        self.clauses.insert(clause);
+
+        self.spans.push((self.literals.len(), clause.len()));
+        self.literals.extend(clause);
+        self.removed.push(false);
+        self.live += 1;
+        Some(id)
     }
 
-    pub fn get_clauses(&mut self) -> &mut HashSet<Vec<Literal>> {
-        &mut self.clauses
+    // Returns false if the clause was already removed. Its literals stay in
+    // the arena.
+    pub fn remove_clause(&mut self, id: ClauseId) -> bool {
+        if self.removed[id.0] {
+            return false;
+        }
+        let hash = Self::hash(self.clause(id));
+        let (start, len) = self.spans[id.0];
+        for l in self.literals[start..start + len].iter() {
+            let occurrences = &mut self.occurrences[l.index()];
+            if let Ok(position) = occurrences.binary_search(&id) {
+                occurrences.remove(position);
+            }
+        }
+        if let Some(duplicates) = &mut self.duplicates {
+            duplicates.remove(hash, id);
+        }
+        self.removed[id.0] = true;
+        self.live -= 1;
+        true
     }
 
-    pub fn clauses(&self) -> &HashSet<Vec<Literal>> {
-        &self.clauses
+    // Literals of the clause, sorted. Also answers for removed clauses.
+    pub fn clause(&self, id: ClauseId) -> &[Literal] {
+        let (start, len) = self.spans[id.0];
+        &self.literals[start..start + len]
     }
 
-    pub fn eval(&self, eval_vec: Vec<Literal>) -> bool {
-        let eval_set = HashSet::<Literal>::from_iter(eval_vec.iter().cloned());
+    pub fn is_removed(&self, id: ClauseId) -> bool {
+        self.removed[id.0]
+    }
+
+    // Id of a live clause with the same literals, in any order.
+    pub fn find(&self, clause: &[Literal]) -> Option<ClauseId> {
+        let mut clause = clause.to_vec();
+        clause.sort();
+        clause.dedup();
+
+        match &self.duplicates {
+            Some(duplicates) => duplicates.find(Self::hash(&clause), |id| self.clause(id) == clause.as_slice()),
+            None => match clause.first() {
+                Some(l) => self.occurrences(*l).iter().cloned().find(|id| self.clause(*id) == clause.as_slice()),
+                None => self.ids().find(|id| self.clause(*id).is_empty()),
+            },
+        }
+    }
+
+    // Ids of the live clauses, in increasing order.
+    pub fn ids(&self) -> impl Iterator<Item = ClauseId> + '_ {
+        (0..self.spans.len()).filter(|i| !self.removed[*i]).map(ClauseId)
+    }
+
+    // Live clauses in the order of their ids.
+    pub fn iter(&self) -> impl Iterator<Item = &[Literal]> + '_ {
+        self.ids().map(|id| self.clause(id))
+    }
+
+    pub fn clauses(&self) -> Clauses<'_> {
+        Clauses { cnf: self }
+    }
 
-        for clause in self.clauses.iter() {
-            if !Self::eval_clause(clause, &eval_set) {
-                return false;
+    // Live clauses containing the literal, by increasing id.
+    pub fn occurrences(&self, l: Literal) -> &[ClauseId] {
+        self.occurrences.get(l.index()).map_or(&[], |o| o.as_slice())
+    }
+
+    // Satisfied when every clause is, falsified when some clause is.
+    pub fn eval(&self, assignment: &Assignment) -> Evaluation {
+        let mut result = Evaluation::Satisfied;
+        for clause in self.iter() {
+            match assignment.eval_clause(clause) {
+                Evaluation::Satisfied => {},
+                Evaluation::Falsified => return Evaluation::Falsified,
+                Evaluation::Undetermined => result = Evaluation::Undetermined,
             }
         }
+        result
+    }
 
-        return true;
+    // The clauses the assignment falsifies and those it leaves undetermined.
+    pub fn diagnose(&self, assignment: &Assignment) -> Diagnostics {
+        let mut diagnostics = Diagnostics::default();
+        for id in self.ids() {
+            match assignment.eval_clause(self.clause(id)) {
+                Evaluation::Satisfied => {},
+                Evaluation::Falsified => diagnostics.falsified.push(id),
+                Evaluation::Undetermined => diagnostics.unresolved.push(id),
+            }
+        }
+        diagnostics
     }
 
-    fn eval_clause(clause: &Vec<Literal>, eval_set: &HashSet<Literal>) -> bool {
-        for l in clause.iter() {
-            if eval_set.contains(l) {
-                return true;
+    fn hash(clause: &[Literal]) -> u64 {
+        FixedState::default().hash_one(clause)
+    }
+}
+
+impl<'a> Clauses<'a> {
+    pub fn iter(&self) -> impl Iterator<Item = &'a [Literal]> + 'a {
+        self.cnf.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.cnf.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cnf.is_empty()
+    }
+
+    pub fn contains(&self, clause: &[Literal]) -> bool {
+        self.cnf.find(clause).is_some()
+    }
+}
+
+impl Duplicates {
+    fn find(&self, hash: u64, equal: impl Fn(ClauseId) -> bool) -> Option<ClauseId> {
+        match self.first.get(&hash) {
+            Some(id) if equal(*id) => Some(*id),
+            Some(_) => self.collisions.iter().filter(|(h, _)| *h == hash).map(|(_, id)| *id).find(|id| equal(*id)),
+            None => None,
+        }
+    }
+
+    fn insert(&mut self, hash: u64, id: ClauseId) {
+        match self.first.entry(hash) {
+            Entry::Occupied(_) => self.collisions.push((hash, id)),
+            Entry::Vacant(entry) => {
+                entry.insert(id);
+            },
+        }
+    }
+
+    fn remove(&mut self, hash: u64, id: ClauseId) {
+        if let Some(position) = self.collisions.iter().position(|entry| *entry == (hash, id)) {
+            self.collisions.swap_remove(position);
+        } else if self.first.get(&hash) == Some(&id) {
+            // Another clause with the same hash takes its place.
+            match self.collisions.iter().position(|(h, _)| *h == hash) {
+                Some(position) => {
+                    let (_, other) = self.collisions.swap_remove(position);
+                    self.first.insert(hash, other);
+                },
+                None => {
+                    self.first.remove(&hash);
+                },
             }
         }
-        return false;
     }
 }
@@ -0,0 +1,10 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::BuildHasherDefault;
+
+// std hash collections with a fixed hasher instead of a randomly keyed one.
+// Iteration order then only depends on what was inserted and in which order,
+// so two runs on the same input walk the clauses the same way.
+pub type FixedState = BuildHasherDefault<DefaultHasher>;
+
+pub type HashSet<T> = std::collections::HashSet<T, FixedState>;
+pub type HashMap<K, V> = std::collections::HashMap<K, V, FixedState>;
@@ -0,0 +1,105 @@
+use super::assignment::Assignment;
+use super::cnf::Var;
+use super::collections::HashMap;
+
+// Names of variables, for formulas written over named options. A name
+// always stands for the same variable. Auxiliary variables are allocated
+// fresh, above every variable taken so far, and have no name.
+#[derive(Clone, Debug, Default)]
+pub struct SymbolTable {
+    names: Vec<Option<String>>,  // by variable, index 0 unused
+    vars: HashMap<String, Var>,
+}
+
+impl SymbolTable {
+    pub fn new() -> SymbolTable {
+        SymbolTable::default()
+    }
+
+    // Largest variable taken, named or not.
+    pub fn var_count(&self) -> usize {
+        self.names.len().saturating_sub(1)
+    }
+
+    // Number of named variables.
+    pub fn len(&self) -> usize {
+        self.vars.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vars.is_empty()
+    }
+
+    // Marks the variables up to var_count as taken, e.g. by the clauses of a
+    // CNF, so that new names and fresh variables come after them.
+    pub fn reserve(&mut self, var_count: usize) {
+        if self.names.len() <= var_count {
+            self.names.resize(var_count + 1, None);
+        }
+    }
+
+    // Variable of the name, allocated on first use.
+    pub fn var(&mut self, name: &str) -> Var {
+        match self.vars.get(name) {
+            Some(var) => *var,
+            None => {
+                let var = self.fresh();
+                self.names[var.index()] = Some(name.to_string());
+                self.vars.insert(name.to_string(), var);
+                var
+            },
+        }
+    }
+
+    // Variable of the name, None if it was never given.
+    pub fn get(&self, name: &str) -> Option<Var> {
+        self.vars.get(name).cloned()
+    }
+
+    // A new variable without a name.
+    pub fn fresh(&mut self) -> Var {
+        let var = Var::new(self.names.len().max(1));
+        self.reserve(var.index());
+        var
+    }
+
+    // Whether the name can be written to a `c var` comment and read back:
+    // a single word, nonempty and without whitespace.
+    pub fn is_valid_name(name: &str) -> bool {
+        !name.is_empty() && !name.contains(char::is_whitespace)
+    }
+
+    // Names a variable taken or not yet. False, changing nothing, if the name
+    // is not valid or the variable or the name already belongs to another pair.
+    pub fn set_name(&mut self, var: Var, name: &str) -> bool {
+        if !SymbolTable::is_valid_name(name) {
+            return false;
+        }
+        if let Some(other) = self.get(name) {
+            return other == var;
+        }
+        if self.name(var).is_some() {
+            return false;
+        }
+        self.reserve(var.index());
+        self.names[var.index()] = Some(name.to_string());
+        self.vars.insert(name.to_string(), var);
+        true
+    }
+
+    pub fn name(&self, var: Var) -> Option<&str> {
+        self.names.get(var.index()).and_then(|name| name.as_deref())
+    }
+
+    // Named variables, ordered by variable.
+    pub fn iter(&self) -> impl Iterator<Item = (Var, &str)> + '_ {
+        self.names.iter()
+            .enumerate()
+            .filter_map(|(v, name)| name.as_deref().map(|name| (Var::new(v), name)))
+    }
+
+    // Values of the named variables the model assigns, ordered by variable.
+    pub fn named_values<'a>(&'a self, model: &'a Assignment) -> impl Iterator<Item = (&'a str, bool)> + 'a {
+        self.iter().filter_map(|(var, name)| model.value(var.positive()).map(|value| (name, value)))
+    }
+}
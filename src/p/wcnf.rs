@@ -0,0 +1,74 @@
+use super::assignment::Assignment;
+use super::cnf::{CNF, Literal};
+
+// Weighted partial MaxSAT instance: hard clauses must be satisfied, and the
+// weights of the falsified soft clauses add up to the cost of an assignment.
+#[derive(Clone, Debug, Default)]
+pub struct WCNF {
+    hard: Vec<Vec<Literal>>,
+    soft: Vec<(Vec<Literal>, u64)>,
+    variables: usize,
+}
+
+impl WCNF {
+    pub fn new() -> WCNF {
+        WCNF::default()
+    }
+
+    // Variable with the largest index
+    pub fn var_count(&self) -> usize {
+        self.variables
+    }
+
+    pub fn add_hard(&mut self, clause: Vec<Literal>) {
+        self.note_variables(&clause);
+        self.hard.push(clause);
+    }
+
+    // Soft clauses of weight 0 never add to the cost, they are dropped.
+    pub fn add_soft(&mut self, clause: Vec<Literal>, weight: u64) {
+        if weight > 0 {
+            self.note_variables(&clause);
+            self.soft.push((clause, weight));
+        }
+    }
+
+    pub fn hard(&self) -> &Vec<Vec<Literal>> {
+        &self.hard
+    }
+
+    pub fn soft(&self) -> &Vec<(Vec<Literal>, u64)> {
+        &self.soft
+    }
+
+    // Hard clauses as a CNF
+    pub fn hard_cnf(&self) -> CNF {
+        let mut cnf = CNF::new();
+        for clause in self.hard.iter() {
+            cnf.add_clause(clause.clone());
+        }
+        cnf
+    }
+
+    // Sum of the soft weights, the cost of falsifying every soft clause
+    pub fn total_weight(&self) -> u64 {
+        self.soft.iter().map(|(_, w)| w).sum()
+    }
+
+    // Cost of the assignment, None if it does not satisfy every hard clause.
+    // A clause counts as satisfied once one of its literals is true.
+    pub fn cost(&self, model: &Assignment) -> Option<u64> {
+        let satisfied = |clause: &Vec<Literal>| clause.iter().any(|l| model.value(*l) == Some(true));
+
+        if !self.hard.iter().all(satisfied) {
+            return None;
+        }
+        Some(self.soft.iter().filter(|(c, _)| !satisfied(c)).map(|(_, w)| w).sum())
+    }
+
+    fn note_variables(&mut self, clause: &[Literal]) {
+        for l in clause.iter() {
+            self.variables = self.variables.max(l.get_var());
+        }
+    }
+}
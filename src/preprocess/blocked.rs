@@ -0,0 +1,50 @@
+use super::formula::Formula;
+use super::preprocessor::Statistics;
+use crate::p::cnf::Literal;
+use crate::solvers::sat::limits::{Limits, Usage};
+
+// Literals in more clauses than this are not tried as blocking literals.
+const MAX_OCCURRENCES: usize = 64;
+
+// Blocked clause elimination (Järvisalo, Biere, Heule): a clause C is blocked
+// on its literal l when every resolvent of C on l is a tautology. Removing C
+// preserves satisfiability, and a model falsifying C is repaired by making l
+// true, so C goes on the reconstruction stack with l as witness. Returns
+// whether the formula changed.
+pub(crate) fn run(formula: &mut Formula, limits: &Limits, usage: &mut Usage, statistics: &mut Statistics) -> bool {
+    let mut changed = false;
+    loop {
+        let mut removed = false;
+        for id in formula.live().collect::<Vec<usize>>() {
+            if formula.unsat || limits.check(usage).is_some() {
+                return changed;
+            }
+            if formula.is_removed(id) {
+                continue;
+            }
+            let clause = formula.clause(id).clone();
+            for l in clause.iter() {
+                if formula.is_frozen(l.get_var()) || formula.occurrence_estimate(l.neg()) > MAX_OCCURRENCES {
+                    continue;
+                }
+                if is_blocked(formula, &clause, *l) {
+                    formula.remove_redundant(id, *l);
+                    statistics.blocked += 1;
+                    removed = true;
+                    break;
+                }
+            }
+        }
+        // Removing a clause can block the clauses it resolved with.
+        if !removed {
+            return changed;
+        }
+        changed = true;
+    }
+}
+
+fn is_blocked(formula: &mut Formula, clause: &[Literal], l: Literal) -> bool {
+    formula.occurrences(l.neg()).iter().all(|other| {
+        formula.clause(*other).iter().any(|m| *m != l.neg() && clause.contains(&m.neg()))
+    })
+}
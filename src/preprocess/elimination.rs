@@ -0,0 +1,90 @@
+use super::formula::Formula;
+use super::preprocessor::Statistics;
+use crate::p::cnf::Literal;
+use crate::solvers::sat::limits::{Limits, Usage};
+
+// Variables in more clauses than this are not tried.
+const MAX_OCCURRENCES: usize = 16;
+
+// Resolvents longer than this make the elimination fail.
+const MAX_RESOLVENT: usize = 20;
+
+// Bounded variable elimination (Eén and Biere): a variable x is replaced by
+// all non-tautological resolvents of its clauses on x, when there are no
+// more of them than clauses they replace. The clauses of x go on the
+// reconstruction stack with the literal of x as witness. Returns whether the
+// formula changed.
+pub(crate) fn run(formula: &mut Formula, limits: &Limits, usage: &mut Usage, statistics: &mut Statistics) -> bool {
+    let mut candidates: Vec<usize> = (1..formula.var_count())
+        .filter(|v| !formula.is_frozen(*v) && !formula.is_eliminated(*v))
+        .collect();
+    let cost = |formula: &Formula, v: usize| {
+        let x = Literal::new(v, false);
+        formula.occurrence_estimate(x) + formula.occurrence_estimate(x.neg())
+    };
+    candidates.sort_by_key(|v| std::cmp::Reverse(cost(formula, *v)));
+
+    let mut changed = false;
+    while let Some(v) = candidates.pop() {
+        if formula.unsat || limits.check(usage).is_some() {
+            break;
+        }
+        let x = Literal::new(v, false);
+        if formula.value(x).is_some() {
+            continue;
+        }
+        if eliminate(formula, x) {
+            statistics.eliminated += 1;
+            changed = true;
+            formula.propagate();
+        }
+    }
+    changed
+}
+
+fn eliminate(formula: &mut Formula, x: Literal) -> bool {
+    let positive = formula.occurrences(x);
+    let negative = formula.occurrences(x.neg());
+    if positive.len() + negative.len() > MAX_OCCURRENCES {
+        return false;
+    }
+    // A variable in no clause is left alone: it is free.
+    if positive.is_empty() && negative.is_empty() {
+        return false;
+    }
+
+    let mut resolvents = Vec::new();
+    for p in positive.iter() {
+        for n in negative.iter() {
+            if let Some(resolvent) = resolve(formula.clause(*p), formula.clause(*n), x) {
+                if resolvent.len() > MAX_RESOLVENT || resolvents.len() == positive.len() + negative.len() {
+                    return false;
+                }
+                resolvents.push(resolvent);
+            }
+        }
+    }
+
+    for id in positive.iter() {
+        formula.remove_redundant(*id, x);
+    }
+    for id in negative.iter() {
+        formula.remove_redundant(*id, x.neg());
+    }
+    formula.set_eliminated(x.get_var());
+    for resolvent in resolvents {
+        formula.add(resolvent);
+    }
+    true
+}
+
+// The resolvent of p (with x) and n (with ¬x) on x, None if it is a tautology.
+fn resolve(p: &[Literal], n: &[Literal], x: Literal) -> Option<Vec<Literal>> {
+    let mut resolvent: Vec<Literal> = p.iter().chain(n.iter()).cloned().filter(|l| l.get_var() != x.get_var()).collect();
+    resolvent.sort();
+    resolvent.dedup();
+    if resolvent.windows(2).any(|pair| pair[0].get_var() == pair[1].get_var()) {
+        return None;
+    }
+    Some(resolvent)
+}
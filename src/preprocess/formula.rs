@@ -0,0 +1,200 @@
+use super::reconstruction::Reconstruction;
+use crate::p::cnf::{CNF, Literal};
+
+// The clause database the techniques work on. Removed clauses keep their
+// slot, so clause ids stay valid. Occurrence lists are cleaned lazily: they
+// may still hold clauses that were removed or lost the literal since.
+// Variables fixed at the top level are propagated away and recorded on the
+// reconstruction stack.
+pub(crate) struct Formula {
+    clauses: Vec<Vec<Literal>>,
+    removed: Vec<bool>,
+    occurrences: Vec<Vec<usize>>,
+    values: Vec<Option<bool>>,
+    frozen: Vec<bool>,
+    eliminated: Vec<bool>,
+    queue: Vec<Literal>,
+    pub(crate) unsat: bool,
+    pub(crate) reconstruction: Reconstruction,
+}
+
+impl Formula {
+    pub(crate) fn new(cnf: &CNF, frozen: &[usize]) -> Formula {
+        let n = cnf.var_count().max(frozen.iter().cloned().max().unwrap_or(0)) + 1;
+        let mut formula = Formula {
+            clauses: Vec::new(),
+            removed: Vec::new(),
+            occurrences: vec![Vec::new(); 2 * n],
+            values: vec![None; n],
+            frozen: vec![false; n],
+            eliminated: vec![false; n],
+            queue: Vec::new(),
+            unsat: false,
+            reconstruction: Reconstruction::new(n - 1),
+        };
+        for v in frozen.iter() {
+            formula.frozen[*v] = true;
+        }
+
+        for clause in cnf.clauses().iter() {
+            formula.add(clause.to_vec());
+        }
+        formula.propagate();
+        formula
+    }
+
+
+    pub(crate) fn var_count(&self) -> usize {
+        self.values.len()
+    }
+
+    pub(crate) fn clause(&self, id: usize) -> &Vec<Literal> {
+        &self.clauses[id]
+    }
+
+    pub(crate) fn is_removed(&self, id: usize) -> bool {
+        self.removed[id]
+    }
+
+    pub(crate) fn value(&self, l: Literal) -> Option<bool> {
+        self.values[l.get_var()].map(|v| v != l.is_negative())
+    }
+
+    pub(crate) fn is_frozen(&self, var: usize) -> bool {
+        self.frozen[var]
+    }
+
+    pub(crate) fn is_eliminated(&self, var: usize) -> bool {
+        self.eliminated[var]
+    }
+
+    pub(crate) fn set_eliminated(&mut self, var: usize) {
+        self.eliminated[var] = true;
+    }
+
+    // Ids of the clauses containing l, cleaning the list on the way.
+    pub(crate) fn occurrences(&mut self, l: Literal) -> Vec<usize> {
+        let i = l.index();
+        let (clauses, removed) = (&self.clauses, &self.removed);
+        self.occurrences[i].retain(|id| !removed[*id] && clauses[*id].contains(&l));
+        self.occurrences[i].clone()
+    }
+
+    // Occurrence count, stale entries included, as a cheap estimate.
+    pub(crate) fn occurrence_estimate(&self, l: Literal) -> usize {
+        self.occurrences[l.index()].len()
+    }
+
+    pub(crate) fn live(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.clauses.len()).filter(|id| !self.removed[*id])
+    }
+
+    // Adds a clause simplified by the fixed variables. Satisfied clauses and
+    // tautologies are dropped, units are fixed (call propagate after).
+    // Returns the id of a clause that was stored.
+    pub(crate) fn add(&mut self, clause: Vec<Literal>) -> Option<usize> {
+        let mut clause: Vec<Literal> = clause.into_iter().filter(|l| self.value(*l) != Some(false)).collect();
+        clause.sort();
+        clause.dedup();
+        if clause.iter().any(|l| self.value(*l) == Some(true)) || clause.windows(2).any(|p| p[0].get_var() == p[1].get_var()) {
+            return None;
+        }
+
+        match clause.len() {
+            0 => {
+                self.unsat = true;
+                None
+            },
+            1 => {
+                self.assign(clause[0]);
+                None
+            },
+            _ => {
+                let id = self.clauses.len();
+                for l in clause.iter() {
+                    self.occurrences[l.index()].push(id);
+                }
+                self.clauses.push(clause);
+                self.removed.push(false);
+                Some(id)
+            },
+        }
+    }
+
+    pub(crate) fn remove(&mut self, id: usize) {
+        self.removed[id] = true;
+    }
+
+    // Removes the clause, which is redundant with l as witness: when a model
+    // falsifies it, making l true gives a model of the original.
+    pub(crate) fn remove_redundant(&mut self, id: usize, witness: Literal) {
+        self.reconstruction.push(witness, self.clauses[id].clone());
+        self.removed[id] = true;
+    }
+
+    // Drops literal l from the clause, which may leave a unit or the empty clause.
+    pub(crate) fn strengthen(&mut self, id: usize, l: Literal) {
+        self.clauses[id].retain(|x| *x != l);
+        match self.clauses[id].len() {
+            0 => {
+                self.removed[id] = true;
+                self.unsat = true;
+            },
+            1 => {
+                self.removed[id] = true;
+                let unit = self.clauses[id][0];
+                self.assign(unit);
+            },
+            _ => {},
+        }
+    }
+
+    // Fixes l at the top level, unless it is already fixed.
+    pub(crate) fn assign(&mut self, l: Literal) {
+        match self.value(l) {
+            Some(true) => {},
+            Some(false) => self.unsat = true,
+            None => {
+                self.values[l.get_var()] = Some(!l.is_negative());
+                self.reconstruction.push(l, vec![l]);
+                self.queue.push(l);
+            },
+        }
+    }
+
+    // Removes the clauses satisfied by the fixed literals and the falsified
+    // literals from the others.
+    pub(crate) fn propagate(&mut self) {
+        while let Some(l) = self.queue.pop() {
+            if self.unsat {
+                return;
+            }
+            for id in self.occurrences(l) {
+                self.removed[id] = true;
+            }
+            for id in self.occurrences(l.neg()) {
+                if !self.removed[id] {
+                    self.strengthen(id, l.neg());
+                }
+            }
+        }
+    }
+
+    pub(crate) fn to_cnf(&self) -> CNF {
+        let mut cnf = CNF::new();
+        if self.unsat {
+            cnf.add_clause(Vec::new());
+            return cnf;
+        }
+        for id in self.live() {
+            cnf.add_clause(self.clauses[id].clone());
+        }
+        // Fixed frozen variables stay as units, so constraints added on them later see their value.
+        for (v, value) in self.values.iter().enumerate() {
+            if let (true, Some(value)) = (self.frozen[v], value) {
+                cnf.add_clause(vec![Literal::new(v, !value)]);
+            }
+        }
+        cnf
+    }
+}
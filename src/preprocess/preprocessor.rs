@@ -0,0 +1,144 @@
+use std::cell::Cell;
+
+use super::formula::Formula;
+use super::reconstruction::Reconstruction;
+use super::{blocked, elimination, probing, subsumption};
+use crate::p::cnf::CNF;
+pub use crate::solvers::sat::limits::Limits;
+use crate::solvers::sat::limits::Usage;
+
+// Simplifies a CNF before solving. Fixed variables are propagated first, then
+// each round runs failed literal probing, subsumption with self-subsuming
+// resolution, bounded variable elimination and blocked clause elimination,
+// until a round changes nothing. The result is satisfiable exactly when the
+// original is, and the reconstruction extends its models to the original.
+#[derive(Clone)]
+pub struct Preprocessor {
+    probing: bool,
+    subsumption: bool,
+    elimination: bool,
+    blocked: bool,
+    frozen: Vec<usize>,
+    max_rounds: usize,
+    limits: Limits,
+    statistics: Cell<Statistics>,
+}
+
+pub struct Preprocessed {
+    pub cnf: CNF,  // a single empty clause when found unsatisfiable
+    pub reconstruction: Reconstruction,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Statistics {
+    pub rounds: u64,
+    pub failed_literals: u64,
+    pub subsumed: u64,
+    pub strengthened: u64,  // literals removed by self-subsuming resolution
+    pub eliminated: u64,  // variables
+    pub blocked: u64,  // clauses
+}
+
+impl Statistics {
+    pub fn entries(&self) -> Vec<(&'static str, u64)> {
+        vec![
+            ("rounds", self.rounds),
+            ("failed_literals", self.failed_literals),
+            ("subsumed", self.subsumed),
+            ("strengthened", self.strengthened),
+            ("eliminated", self.eliminated),
+            ("blocked", self.blocked),
+        ]
+    }
+}
+
+impl Default for Preprocessor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Preprocessor {
+    // All techniques are enabled by default.
+    pub fn new() -> Preprocessor {
+        Preprocessor {
+            probing: true,
+            subsumption: true,
+            elimination: true,
+            blocked: true,
+            frozen: Vec::new(),
+            max_rounds: 8,
+            limits: Limits::new(),
+            statistics: Cell::new(Statistics::default()),
+        }
+    }
+
+    pub fn set_probing(&mut self, value: bool) {
+        self.probing = value;
+    }
+
+    pub fn set_subsumption(&mut self, value: bool) {
+        self.subsumption = value;
+    }
+
+    pub fn set_elimination(&mut self, value: bool) {
+        self.elimination = value;
+    }
+
+    pub fn set_blocked(&mut self, value: bool) {
+        self.blocked = value;
+    }
+
+    // Frozen variables keep their clauses as far as models go: they are never
+    // eliminated nor used to block clauses, so they can be assumed or
+    // constrained further after preprocessing.
+    pub fn set_frozen(&mut self, value: Vec<usize>) {
+        self.frozen = value;
+    }
+
+    pub fn set_max_rounds(&mut self, value: usize) {
+        self.max_rounds = value;
+    }
+
+    // When a limit is hit, preprocessing stops early with what it has done so far.
+    pub fn set_limits(&mut self, value: Limits) {
+        self.limits = value;
+    }
+
+    // Statistics of the last call to preprocess.
+    pub fn last_statistics(&self) -> Statistics {
+        self.statistics.get()
+    }
+
+    pub fn preprocess(&self, cnf: &CNF) -> Preprocessed {
+        let mut statistics = Statistics::default();
+        let mut formula = Formula::new(cnf, &self.frozen);
+        let mut usage = Usage::default();
+
+        while (statistics.rounds as usize) < self.max_rounds && !formula.unsat && self.limits.check(&mut usage).is_none() {
+            statistics.rounds += 1;
+            let mut changed = false;
+            if self.probing {
+                changed |= probing::run(&mut formula, &self.limits, &mut usage, &mut statistics);
+            }
+            if self.subsumption {
+                changed |= subsumption::run(&mut formula, &self.limits, &mut usage, &mut statistics);
+            }
+            if self.elimination {
+                changed |= elimination::run(&mut formula, &self.limits, &mut usage, &mut statistics);
+            }
+            if self.blocked {
+                changed |= blocked::run(&mut formula, &self.limits, &mut usage, &mut statistics);
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        self.statistics.set(statistics);
+        Preprocessed {
+            cnf: formula.to_cnf(),
+            reconstruction: formula.reconstruction,
+        }
+    }
+}
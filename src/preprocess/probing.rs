@@ -0,0 +1,100 @@
+use super::formula::Formula;
+use super::preprocessor::Statistics;
+use crate::p::cnf::Literal;
+use crate::solvers::sat::limits::{Limits, Usage};
+
+// Propagated literals per round, over all probes.
+const MAX_PROPAGATIONS: usize = 1 << 20;
+
+// Failed literal probing: a literal l whose unit propagation falsifies a
+// clause is failed, so ¬l holds in every model and is fixed. Only literals
+// of binary clauses are probed, the others rarely propagate anything.
+// Returns whether the formula changed.
+pub(crate) fn run(formula: &mut Formula, limits: &Limits, usage: &mut Usage, statistics: &mut Statistics) -> bool {
+    let mut probes: Vec<Literal> = Vec::new();
+    let mut seen = vec![false; 2 * formula.var_count()];
+    for id in formula.live() {
+        if formula.clause(id).len() == 2 {
+            for l in formula.clause(id).iter() {
+                // Propagating ¬l makes the other literal of the clause true.
+                let probe = l.neg();
+                if !seen[probe.index()] {
+                    seen[probe.index()] = true;
+                    probes.push(probe);
+                }
+            }
+        }
+    }
+    probes.reverse();
+
+    let mut propagations = 0;
+    let mut changed = false;
+    let mut values: Vec<Option<bool>> = vec![None; formula.var_count()];
+    while let Some(probe) = probes.pop() {
+        if formula.unsat || propagations > MAX_PROPAGATIONS || limits.check(usage).is_some() {
+            break;
+        }
+        if formula.value(probe).is_some() {
+            continue;
+        }
+
+        let (conflict, trail) = propagate(formula, &mut values, probe);
+        propagations += trail.len();
+        for l in trail {
+            values[l.get_var()] = None;
+        }
+        if conflict {
+            formula.assign(probe.neg());
+            formula.propagate();
+            statistics.failed_literals += 1;
+            changed = true;
+        }
+    }
+    changed
+}
+
+// Unit propagation of l over the formula, with the temporary assignment in
+// `values` on top of the fixed one. Returns whether a clause was falsified,
+// and the literals assigned.
+fn propagate(formula: &mut Formula, values: &mut [Option<bool>], l: Literal) -> (bool, Vec<Literal>) {
+    let value = |values: &[Option<bool>], formula: &Formula, l: Literal| {
+        formula.value(l).or(values[l.get_var()].map(|v| v != l.is_negative()))
+    };
+
+    values[l.get_var()] = Some(!l.is_negative());
+    let mut trail = vec![l];
+    let mut head = 0;
+    while head < trail.len() {
+        let falsified = trail[head].neg();
+        head += 1;
+        for id in formula.occurrences(falsified) {
+            let mut unit = None;
+            let mut open = 0;
+            let mut satisfied = false;
+            for m in formula.clause(id).iter() {
+                match value(values, formula, *m) {
+                    Some(true) => {
+                        satisfied = true;
+                        break;
+                    },
+                    Some(false) => {},
+                    None => {
+                        open += 1;
+                        unit = Some(*m);
+                    },
+                }
+            }
+            if satisfied || open > 1 {
+                continue;
+            }
+            match unit {
+                None => return (true, trail),
+                Some(u) => {
+                    values[u.get_var()] = Some(!u.is_negative());
+                    trail.push(u);
+                },
+            }
+        }
+    }
+    (false, trail)
+}
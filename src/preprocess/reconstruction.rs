@@ -0,0 +1,54 @@
+use crate::p::assignment::Assignment;
+use crate::p::cnf::Literal;
+
+// Turns a model of the preprocessed CNF into a model of the original one.
+// Every clause a technique removed without preserving equivalence is pushed
+// with a witness literal of the clause. Going through the stack backwards,
+// a clause the model falsifies is repaired by making its witness true, which
+// keeps the clauses pushed after it satisfied. Fixed variables are pushed
+// as unit clauses.
+#[derive(Clone, Debug, Default)]
+pub struct Reconstruction {
+    stack: Vec<(Literal, Vec<Literal>)>,
+    variables: usize,
+}
+
+impl Reconstruction {
+    pub(crate) fn new(variables: usize) -> Reconstruction {
+        Reconstruction {
+            stack: Vec::new(),
+            variables,
+        }
+    }
+
+    pub(crate) fn push(&mut self, witness: Literal, clause: Vec<Literal>) {
+        self.stack.push((witness, clause));
+    }
+
+    pub fn len(&self) -> usize {
+        self.stack.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stack.is_empty()
+    }
+
+    // Extends a model of the preprocessed CNF, possibly partial, to a model
+    // of the original: a value for every variable, frozen ones included.
+    // Variables the model leaves unassigned are taken as false.
+    pub fn extend(&self, model: &Assignment) -> Assignment {
+        let n = model.var_count().max(self.variables) + 1;
+        let mut values = vec![false; n];
+        for l in model.literals() {
+            values[l.get_var()] = !l.is_negative();
+        }
+
+        for (witness, clause) in self.stack.iter().rev() {
+            if !clause.iter().any(|l| values[l.get_var()] != l.is_negative()) {
+                values[witness.get_var()] = !witness.is_negative();
+            }
+        }
+
+        Assignment::from_values(values.into_iter().take(self.variables + 1).map(Some).collect())
+    }
+}
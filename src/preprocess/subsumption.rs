@@ -0,0 +1,83 @@
+use super::formula::Formula;
+use super::preprocessor::Statistics;
+use crate::p::cnf::Literal;
+use crate::solvers::sat::limits::{Limits, Usage};
+
+// Backward subsumption and self-subsuming resolution. A clause C removes
+// every clause D containing it. When C with one literal l flipped is in D,
+// resolving them on l gives D without ¬l, which replaces D. Both preserve
+// equivalence, so nothing goes on the reconstruction stack. Returns whether
+// the formula changed.
+pub(crate) fn run(formula: &mut Formula, limits: &Limits, usage: &mut Usage, statistics: &mut Statistics) -> bool {
+    // Short clauses first, they subsume the most.
+    let mut queue: Vec<usize> = formula.live().collect();
+    queue.sort_by_key(|id| std::cmp::Reverse(formula.clause(*id).len()));
+
+    let mut changed = false;
+    while let Some(id) = queue.pop() {
+        if formula.unsat || limits.check(usage).is_some() {
+            break;
+        }
+        if formula.is_removed(id) {
+            continue;
+        }
+
+        // Every clause C can act on contains p or ¬p for each of its literals p,
+        // so the rarest variable gives the fewest candidates.
+        let clause = formula.clause(id).clone();
+        let p = *clause.iter()
+            .min_by_key(|l| formula.occurrence_estimate(**l) + formula.occurrence_estimate(l.neg()))
+            .unwrap();
+        let mut candidates = formula.occurrences(p);
+        candidates.extend(formula.occurrences(p.neg()));
+
+        for other in candidates {
+            if other == id || formula.is_removed(other) || formula.clause(other).len() < clause.len() {
+                continue;
+            }
+            match subsumes(&clause, formula.clause(other)) {
+                Some(None) => {
+                    formula.remove(other);
+                    statistics.subsumed += 1;
+                    changed = true;
+                },
+                Some(Some(l)) => {
+                    formula.strengthen(other, l.neg());
+                    statistics.strengthened += 1;
+                    changed = true;
+                    // The shorter clause may subsume others in turn.
+                    if !formula.is_removed(other) {
+                        queue.push(other);
+                    }
+                },
+                None => {},
+            }
+        }
+        formula.propagate();
+    }
+    changed
+}
+
+// Some(None) when c is a subset of d. Some(Some(l)) when c with l flipped
+// is, for exactly one literal l of c. Both clauses are sorted.
+fn subsumes(c: &[Literal], d: &[Literal]) -> Option<Option<Literal>> {
+    let mut flipped = None;
+    let mut j = 0;
+    for l in c.iter() {
+        // Literals of a variable are adjacent in a sorted clause.
+        while j < d.len() && d[j].get_var() < l.get_var() {
+            j += 1;
+        }
+        if j == d.len() || d[j].get_var() != l.get_var() {
+            return None;
+        }
+        if d[j] != *l {
+            if flipped.is_some() {
+                return None;
+            }
+            flipped = Some(*l);
+        }
+        j += 1;
+    }
+    Some(flipped)
+}
@@ -0,0 +1,544 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, Read, Write};
+
+use crate::p::cnf::{CNF, Literal, Var};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DratFormat {
+    Text,
+    Binary,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Step {
+    Add(Vec<Literal>),
+    Delete(Vec<Literal>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CheckError {
+    NoConflict,
+    LemmaFailed { step: usize, clause: Vec<Literal> },
+}
+
+// Anything a solver can report its derivation steps to.
+pub trait ProofTracer {
+    fn add(&mut self, clause: &[Literal]) -> io::Result<()>;
+    fn delete(&mut self, clause: &[Literal]) -> io::Result<()>;
+}
+
+pub struct DratWriter<W: Write> {
+    sink: W,
+    format: DratFormat,
+}
+
+impl<W: Write> DratWriter<W> {
+    pub fn new(sink: W, format: DratFormat) -> DratWriter<W> {
+        DratWriter { sink, format }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.sink
+    }
+
+    fn write_step(&mut self, prefix: Option<u8>, clause: &[Literal]) -> io::Result<()> {
+        match self.format {
+            DratFormat::Text => {
+                if prefix.is_some() {
+                    write!(self.sink, "d ")?;
+                }
+                for l in clause.iter() {
+                    write!(self.sink, "{:?} ", l)?;
+                }
+                writeln!(self.sink, "0")
+            },
+            DratFormat::Binary => {
+                let mut bytes = vec![prefix.unwrap_or(b'a')];
+                for l in clause.iter() {
+                    let mut u = l.index();
+                    while u > 127 {
+                        bytes.push((u & 127) as u8 | 128);
+                        u >>= 7;
+                    }
+                    bytes.push(u as u8);
+                }
+                bytes.push(0);
+                self.sink.write_all(&bytes)
+            },
+        }
+    }
+}
+
+impl<W: Write> ProofTracer for DratWriter<W> {
+    fn add(&mut self, clause: &[Literal]) -> io::Result<()> {
+        self.write_step(None, clause)
+    }
+
+    fn delete(&mut self, clause: &[Literal]) -> io::Result<()> {
+        self.write_step(Some(b'd'), clause)
+    }
+}
+
+impl std::fmt::Display for CheckError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CheckError::NoConflict => write!(f, "proof does not derive the empty clause"),
+            CheckError::LemmaFailed { step, clause } => {
+                write!(f, "lemma at step {} is neither RUP nor RAT: {:?}", step, clause)
+            },
+        }
+    }
+}
+
+impl std::error::Error for CheckError {}
+
+impl DratFormat {
+    // As drat-trim does: binary if a byte that is neither printable ASCII nor
+    // whitespace shows up early on. Binary steps start with 'a' or 'd' and end
+    // with a 0 byte. Leading comment lines are skipped first, as only text
+    // proofs have them and they may hold any bytes.
+    pub fn detect(bytes: &[u8]) -> DratFormat {
+        let mut rest = bytes;
+        while rest.first() == Some(&b'c') {
+            rest = match rest.iter().position(|b| *b == b'\n') {
+                Some(end) => &rest[end + 1..],
+                None => &[],
+            };
+        }
+
+        let textual = rest.iter().take(64).all(|b| b.is_ascii_graphic() || b.is_ascii_whitespace());
+
+        if textual { DratFormat::Text } else { DratFormat::Binary }
+    }
+}
+
+pub fn read_proof<R: Read>(mut reader: R) -> io::Result<Vec<Step>> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+
+    match DratFormat::detect(&bytes) {
+        DratFormat::Text => parse_text(&bytes[..]),
+        DratFormat::Binary => parse_binary(&bytes),
+    }
+}
+
+pub fn parse_text<R: BufRead>(reader: R) -> io::Result<Vec<Step>> {
+    let mut steps = Vec::new();
+
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('c') {
+            continue;
+        }
+
+        let (delete, body) = match line.strip_prefix('d') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+
+        let mut clause = Vec::new();
+        for token in body.split_whitespace() {
+            if token == "0" {
+                break;
+            }
+            match Literal::parse(token) {
+                Some(l) => clause.push(l),
+                None => {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, "Failed to parse proof literal"))
+                }
+            }
+        }
+
+        steps.push(if delete { Step::Delete(clause) } else { Step::Add(clause) });
+    }
+
+    Ok(steps)
+}
+
+pub fn parse_binary(bytes: &[u8]) -> io::Result<Vec<Step>> {
+    let mut steps = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let delete = match bytes[i] {
+            b'a' => false,
+            b'd' => true,
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "Unexpected byte in binary proof")),
+        };
+        i += 1;
+
+        let mut clause = Vec::new();
+        loop {
+            let mut u: usize = 0;
+            let mut shift = 0;
+            loop {
+                if i >= bytes.len() {
+                    return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Truncated binary proof"));
+                }
+                // Literals take up to 32 bits, so 5 bytes of 7 bits each.
+                if shift > 28 {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, "Binary proof literal out of range"));
+                }
+                let b = bytes[i];
+                i += 1;
+                u |= ((b & 127) as usize) << shift;
+                shift += 7;
+                if b & 128 == 0 {
+                    break;
+                }
+            }
+
+            if u == 0 {
+                break;
+            }
+            if u < 2 || u / 2 > Var::MAX {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "Binary proof literal out of range"));
+            }
+            clause.push(Literal::from_index(u));
+        }
+
+        steps.push(if delete { Step::Delete(clause) } else { Step::Add(clause) });
+    }
+
+    Ok(steps)
+}
+
+struct Checker {
+    clauses: Vec<Vec<Literal>>,
+    active: Vec<bool>,
+    marked: Vec<bool>,
+    pivots: Vec<Option<Literal>>,
+    occurrences: Vec<Vec<usize>>,
+    watches: Vec<Vec<usize>>,
+    short: Vec<usize>,
+
+    assigns: Vec<Option<bool>>,
+    reason: Vec<Option<usize>>,
+    touched: Vec<usize>,
+}
+
+impl Checker {
+
+    fn new(n: usize) -> Checker {
+        Checker {
+            clauses: Vec::new(),
+            active: Vec::new(),
+            marked: Vec::new(),
+            pivots: Vec::new(),
+            occurrences: vec![Vec::new(); 2 * n],
+            watches: vec![Vec::new(); 2 * n],
+            short: Vec::new(),
+            assigns: vec![None; n],
+            reason: vec![None; n],
+            touched: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, clause: Vec<Literal>) -> usize {
+        let c = self.clauses.len();
+        for l in clause.iter() {
+            self.occurrences[l.index()].push(c);
+        }
+        if clause.len() <= 1 {
+            self.short.push(c);
+        } else {
+            self.watches[clause[0].index()].push(c);
+            self.watches[clause[1].index()].push(c);
+        }
+        self.pivots.push(clause.first().cloned());
+        self.clauses.push(clause);
+        self.active.push(true);
+        self.marked.push(false);
+        c
+    }
+
+    fn value(&self, l: Literal) -> Option<bool> {
+        self.assigns[l.get_var()].map(|v| v != l.is_negative())
+    }
+
+    fn assign(&mut self, l: Literal, reason: Option<usize>, queue: &mut Vec<Literal>) {
+        self.assigns[l.get_var()] = Some(!l.is_negative());
+        self.reason[l.get_var()] = reason;
+        self.touched.push(l.get_var());
+        queue.push(l);
+    }
+
+    fn reset(&mut self) {
+        for v in self.touched.drain(..) {
+            self.assigns[v] = None;
+            self.reason[v] = None;
+        }
+    }
+
+    // Assigns every literal of the clause to false and propagates over
+    // the active clauses. On conflict returns the clauses that were used.
+    // Every check starts from an empty assignment, so the watched pairs stay
+    // valid between checks no matter which clauses are (de)activated.
+    fn rup(&mut self, clause: &[Literal]) -> Option<Vec<usize>> {
+        let result = self.propagate_negation(clause);
+        self.reset();
+        result
+    }
+
+    fn propagate_negation(&mut self, clause: &[Literal]) -> Option<Vec<usize>> {
+        let mut queue = Vec::new();
+
+        for l in clause.iter() {
+            match self.value(*l) {
+                Some(true) => return Some(Vec::new()),
+                Some(false) => {},
+                None => self.assign(l.neg(), None, &mut queue),
+            }
+        }
+
+        for k in 0..self.short.len() {
+            let c = self.short[k];
+            if !self.active[c] {
+                continue;
+            }
+            if self.clauses[c].is_empty() {
+                return Some(self.core(c));
+            }
+            let l = self.clauses[c][0];
+            match self.value(l) {
+                Some(true) => {},
+                Some(false) => return Some(self.core(c)),
+                None => self.assign(l, Some(c), &mut queue),
+            }
+        }
+
+        let mut head = 0;
+        while head < queue.len() {
+            let falsified = queue[head].neg();
+            head += 1;
+
+            let mut ws = std::mem::take(&mut self.watches[falsified.index()]);
+            let mut conflict = None;
+            let mut kept = 0;
+
+            for i in 0..ws.len() {
+                let c = ws[i];
+                if conflict.is_some() || !self.active[c] {
+                    ws[kept] = c;
+                    kept += 1;
+                    continue;
+                }
+
+                if self.clauses[c][0] == falsified {
+                    self.clauses[c].swap(0, 1);
+                }
+
+                let first = self.clauses[c][0];
+                if self.value(first) == Some(true) {
+                    ws[kept] = c;
+                    kept += 1;
+                    continue;
+                }
+
+                let replacement = (2..self.clauses[c].len())
+                    .find(|&k| self.value(self.clauses[c][k]) != Some(false));
+                if let Some(k) = replacement {
+                    self.clauses[c].swap(1, k);
+                    self.watches[self.clauses[c][1].index()].push(c);
+                    continue;
+                }
+
+                ws[kept] = c;
+                kept += 1;
+                match self.value(first) {
+                    Some(false) => conflict = Some(c),
+                    _ => self.assign(first, Some(c), &mut queue),
+                }
+            }
+
+            ws.truncate(kept);
+            self.watches[falsified.index()] = ws;
+
+            if let Some(c) = conflict {
+                return Some(self.core(c));
+            }
+        }
+
+        None
+    }
+
+    // Collects the conflicting clause together with all reasons it depends on.
+    fn core(&self, conflict: usize) -> Vec<usize> {
+        let mut core = vec![conflict];
+        let mut seen = HashMap::<usize, bool>::new();
+        let mut stack: Vec<Literal> = self.clauses[conflict].clone();
+
+        while let Some(l) = stack.pop() {
+            let v = l.get_var();
+            if seen.insert(v, true).is_some() {
+                continue;
+            }
+            if let Some(r) = self.reason[v] {
+                core.push(r);
+                stack.extend(self.clauses[r].iter().cloned());
+            }
+        }
+
+        core
+    }
+
+    fn mark(&mut self, core: Vec<usize>) {
+        for c in core.into_iter() {
+            self.marked[c] = true;
+        }
+    }
+
+    fn check_rat(&mut self, lemma: &[Literal], pivot: Option<Literal>) -> Option<Vec<usize>> {
+        let pivot = pivot?;
+        let mut used = Vec::new();
+
+        let candidates = self.occurrences[pivot.neg().index()].clone();
+        for c in candidates.into_iter() {
+            if !self.active[c] {
+                continue;
+            }
+
+            let mut resolvent: Vec<Literal> = lemma.to_vec();
+            resolvent.extend(self.clauses[c].iter().cloned().filter(|l| *l != pivot.neg()));
+
+            let mut core = self.rup(&resolvent)?;
+            used.push(c);
+            used.append(&mut core);
+        }
+
+        Some(used)
+    }
+}
+
+fn sorted(clause: &[Literal]) -> Vec<Literal> {
+    let mut key = clause.to_vec();
+    key.sort();
+    key.dedup();
+    key
+}
+
+enum Event {
+    Added { step: usize, clause: usize },
+    Deleted { clause: usize },
+}
+
+// Backward DRAT checking: replays the proof up to the empty clause, then walks
+// it backwards verifying (by RUP, falling back to RAT) only the lemmas that
+// were actually used to derive the conflict.
+pub fn check(cnf: &CNF, steps: &[Step]) -> Result<(), CheckError> {
+    let mut n = cnf.var_count() + 1;
+    for step in steps.iter() {
+        let (Step::Add(clause) | Step::Delete(clause)) = step;
+        for l in clause.iter() {
+            n = n.max(l.get_var() + 1);
+        }
+    }
+
+    let mut checker = Checker::new(n);
+    let mut by_literals = HashMap::<Vec<Literal>, Vec<usize>>::new();
+
+    for clause in cnf.clauses().iter() {
+        let c = checker.push(clause.to_vec());
+        by_literals.entry(sorted(clause)).or_default().push(c);
+    }
+
+    let mut events = Vec::new();
+    let mut refutation = None;
+
+    for (i, step) in steps.iter().enumerate() {
+        match step {
+            Step::Add(clause) if clause.is_empty() => {
+                refutation = Some(i);
+                break;
+            },
+            Step::Add(clause) => {
+                let c = checker.push(clause.clone());
+                by_literals.entry(sorted(clause)).or_default().push(c);
+                events.push(Event::Added { step: i, clause: c });
+            },
+            Step::Delete(clause) => {
+                // Unit deletions are ignored, as drat-trim does by default.
+                if clause.len() <= 1 {
+                    continue;
+                }
+                if let Some(c) = by_literals.get_mut(&sorted(clause)).and_then(|ids| ids.pop()) {
+                    checker.active[c] = false;
+                    events.push(Event::Deleted { clause: c });
+                }
+            },
+        }
+    }
+
+    match (checker.rup(&[]), refutation) {
+        (Some(core), _) => checker.mark(core),
+        (None, Some(step)) => return Err(CheckError::LemmaFailed { step, clause: Vec::new() }),
+        (None, None) => return Err(CheckError::NoConflict),
+    }
+
+    for event in events.into_iter().rev() {
+        let (step, c) = match event {
+            Event::Deleted { clause } => {
+                checker.active[clause] = true;
+                continue;
+            },
+            Event::Added { step, clause } => (step, clause),
+        };
+
+        checker.active[c] = false;
+        if !checker.marked[c] {
+            continue;
+        }
+
+        let lemma = checker.clauses[c].clone();
+        let core = match checker.rup(&lemma) {
+            Some(core) => Some(core),
+            None => checker.check_rat(&lemma, checker.pivots[c]),
+        };
+
+        match core {
+            Some(core) => checker.mark(core),
+            None => return Err(CheckError::LemmaFailed { step, clause: lemma }),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commented_text_proof_round_trips() {
+        let proof = "c generated by cadical\nc r\u{e9}sum\u{e9}\n1 -2 0\nc lemma\nd 1 -2 0\n0\n";
+        let l = Literal::from_int;
+        let steps = vec![Step::Add(vec![l(1), l(-2)]), Step::Delete(vec![l(1), l(-2)]), Step::Add(Vec::new())];
+
+        assert_eq!(DratFormat::detect(proof.as_bytes()), DratFormat::Text);
+        assert_eq!(read_proof(proof.as_bytes()).unwrap(), steps);
+
+        let mut writer = DratWriter::new(b"c written back\n".to_vec(), DratFormat::Text);
+        for step in steps.iter() {
+            match step {
+                Step::Add(clause) => writer.add(clause).unwrap(),
+                Step::Delete(clause) => writer.delete(clause).unwrap(),
+            }
+        }
+        assert_eq!(read_proof(&writer.into_inner()[..]).unwrap(), steps);
+    }
+
+    #[test]
+    fn overlong_binary_literal_is_invalid_data() {
+        let mut proof = vec![b'a'];
+        proof.extend([0xFF; 12]);
+        proof.push(0);
+        assert_eq!(parse_binary(&proof).unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        // 2 * (Var::MAX + 1) fits in 5 bytes, but not in the literal range.
+        let proof = vec![b'a', 0x80, 0x80, 0x80, 0x80, 0x10, 0];
+        assert_eq!(parse_binary(&proof).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        let proof = [b'a', 0xFF, 0xFF, 0xFF, 0xFF, 0x0F, 0];
+        assert_eq!(parse_binary(&proof).unwrap(), vec![Step::Add(vec![Literal::new(Var::MAX, true)])]);
+    }
+}
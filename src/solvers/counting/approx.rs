@@ -0,0 +1,303 @@
+use std::cell::Cell;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use super::cell::Cells;
+use super::xor::Xor;
+use crate::p::bigint::BigUint;
+use crate::p::cnf::CNF;
+use crate::solvers::sat::cdcl::IncrementalCDCL;
+use crate::solvers::sat::limits::Limits;
+pub use crate::solvers::sat::solver::{IncrementalSolver, Error};
+
+// Approximate model counter in the style of ApproxMC (Chakraborty, Meel,
+// Vardi). Random XOR constraints over the sampling set split the models into
+// cells of about the same size. A round finds the number m of constraints at
+// which a cell gets smaller than a threshold, and the size of that cell times
+// 2^m estimates the count. The median over all rounds is within a factor of
+// (1 + epsilon) of the count with probability at least 1 - delta.
+pub struct ApproxCounter {
+    epsilon: f64,
+    delta: f64,
+    sampling_set: Option<Vec<usize>>,
+    seed: Option<u64>,
+    limits: Limits,
+
+    statistics: Cell<Statistics>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Estimate {
+    pub count: BigUint,
+    pub epsilon: f64,
+    pub delta: f64,
+    pub exact: bool,  // there were fewer models than the threshold, all of them counted
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Statistics {
+    pub rounds: u64,
+    pub failed_rounds: u64,  // rounds that ended with an empty cell or ran out of constraints
+    pub xors: u64,
+    pub cells: u64,  // cells counted by enumeration
+    pub solver_calls: u64,
+}
+
+impl Statistics {
+    pub fn entries(&self) -> Vec<(&'static str, u64)> {
+        vec![
+            ("rounds", self.rounds),
+            ("failed_rounds", self.failed_rounds),
+            ("xors", self.xors),
+            ("cells", self.cells),
+            ("solver_calls", self.solver_calls),
+        ]
+    }
+}
+
+struct Search<'a, S: IncrementalSolver, F: Fn() -> S> {
+    new_solver: F,
+    cnf: &'a CNF,
+    limits: &'a Limits,
+    sampling_set: &'a [usize],
+    cells: Cells<'a, S>,
+    threshold: usize,
+    rng: StdRng,
+
+    statistics: Statistics,
+}
+
+impl std::fmt::Display for Estimate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.exact {
+            write!(f, "{} (exact)", self.count)
+        } else {
+            write!(f, "{} (epsilon = {}, delta = {})", self.count, self.epsilon, self.delta)
+        }
+    }
+}
+
+impl Default for ApproxCounter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ApproxCounter {
+    pub fn new() -> ApproxCounter {
+        ApproxCounter {
+            epsilon: 0.8,
+            delta: 0.2,
+            sampling_set: None,
+            seed: None,
+            limits: Limits::new(),
+            statistics: Cell::new(Statistics::default()),
+        }
+    }
+
+    // Tolerance: the estimate is within [count / (1 + epsilon), count * (1 + epsilon)].
+    pub fn set_epsilon(&mut self, value: f64) {
+        self.epsilon = value;
+    }
+
+    // Confidence: the estimate is out of the tolerance with probability at most delta.
+    pub fn set_delta(&mut self, value: f64) {
+        self.delta = value;
+    }
+
+    // Variables the XORs are drawn over, and the models are counted over.
+    // Every variable should be a function of these (an independent support),
+    // otherwise the count is projected onto them.
+    pub fn set_sampling_set(&mut self, value: Vec<usize>) {
+        self.sampling_set = Some(value);
+    }
+
+    pub fn set_seed(&mut self, value: u64) {
+        self.seed = Some(value);
+    }
+
+    // Applied to every solver call, a deadline or cancellation covers the whole count.
+    pub fn set_limits(&mut self, value: Limits) {
+        self.limits = value;
+    }
+
+    // Statistics of the last call to count.
+    pub fn last_statistics(&self) -> Statistics {
+        self.statistics.get()
+    }
+
+    // Cell size below which cells are counted, 1 + 9.84 (1 + e/(1+e)) (1 + 1/e)^2.
+    pub fn threshold(&self) -> usize {
+        let e = self.epsilon;
+        (1.0 + 9.84 * (1.0 + e / (1.0 + e)) * (1.0 + 1.0 / e).powi(2)).ceil() as usize
+    }
+
+    // Rounds whose median is taken, 17 log2(3 / delta).
+    pub fn rounds(&self) -> usize {
+        (17.0 * (3.0 / self.delta).log2()).ceil() as usize
+    }
+
+    pub fn count(&self, cnf: &CNF) -> Result<Estimate, Error> {
+        self.count_with(IncrementalCDCL::new, cnf)
+    }
+
+    // Every round starts over with a solver from new_solver, so the XORs and
+    // blocking clauses of earlier rounds do not pile up.
+    pub fn count_with<S: IncrementalSolver, F: Fn() -> S>(&self, new_solver: F, cnf: &CNF) -> Result<Estimate, Error> {
+        if self.epsilon.is_nan() || self.epsilon <= 0.0 {
+            return Err(Error::InvalidInput(format!("epsilon must be positive, got {}", self.epsilon)));
+        }
+        if !(self.delta > 0.0 && self.delta < 1.0) {
+            return Err(Error::InvalidInput(format!("delta must be in (0, 1), got {}", self.delta)));
+        }
+
+        let sampling_set: Vec<usize> = match &self.sampling_set {
+            Some(sampling_set) => {
+                let mut variables = sampling_set.clone();
+                variables.sort();
+                variables.dedup();
+                variables
+            },
+            None => (1..=cnf.var_count()).collect(),
+        };
+
+        let mut search = Search {
+            cells: Cells::new(new_solver(), cnf, &sampling_set, &self.limits),
+            new_solver,
+            cnf,
+            limits: &self.limits,
+            sampling_set: &sampling_set,
+            threshold: self.threshold(),
+            rng: match self.seed {
+                Some(seed) => StdRng::seed_from_u64(seed),
+                None => StdRng::from_entropy(),
+            },
+            statistics: Statistics::default(),
+        };
+
+        let count = search.count(self.rounds());
+        search.statistics.solver_calls += search.cells.solver_calls;
+        self.statistics.set(search.statistics);
+
+        Ok(Estimate {
+            count: count?,
+            epsilon: self.epsilon,
+            delta: self.delta,
+            exact: search.statistics.rounds == 0,
+        })
+    }
+}
+
+impl<S: IncrementalSolver, F: Fn() -> S> Search<'_, S, F> {
+    // Every round starts over with a fresh solver.
+    fn restart(&mut self) {
+        let cells = Cells::new((self.new_solver)(), self.cnf, self.sampling_set, self.limits);
+        self.statistics.solver_calls += std::mem::replace(&mut self.cells, cells).solver_calls;
+    }
+
+    fn count(&mut self, rounds: usize) -> Result<BigUint, Error> {
+        let models = self.count_cell(&[])?;
+        if models < self.threshold {
+            return Ok(BigUint::from(models as u64));
+        }
+
+        let mut estimates = Vec::new();
+        let mut guess = 1;
+        for _ in 0..rounds {
+            self.statistics.rounds += 1;
+            match self.round(guess)? {
+                Some((m, size)) if size > 0 => {
+                    estimates.push(&BigUint::from(size as u64) << m);
+                    guess = m;
+                },
+                _ => self.statistics.failed_rounds += 1,
+            }
+        }
+
+        if estimates.is_empty() {
+            return Err(Error::ResourceExhausted("every hashing round failed".to_string()));
+        }
+        estimates.sort();
+        Ok(estimates.swap_remove(estimates.len() / 2))
+    }
+
+    // Draws fresh XORs and finds the smallest m for which the cell of the
+    // first m of them is below the threshold. Cells shrink as constraints are
+    // added, so the search gallops from the guess and then bisects.
+    // Returns m and the size of the cell, None if even all the XORs leave a
+    // cell too large.
+    fn round(&mut self, guess: usize) -> Result<Option<(usize, usize)>, Error> {
+        self.restart();
+        let n = self.sampling_set.len();
+        let mut xors: Vec<Xor> = Vec::new();
+        let mut sizes: Vec<Option<usize>> = vec![None; n + 1];
+
+        let mut size = |search: &mut Self, m: usize| -> Result<usize, Error> {
+            if let Some(size) = sizes[m] {
+                return Ok(size);
+            }
+            while xors.len() < m {
+                xors.push(Xor::random(search.sampling_set, &mut search.rng));
+                search.statistics.xors += 1;
+            }
+
+            let size = search.count_cell(&xors[..m])?;
+            sizes[m] = Some(size);
+            Ok(size)
+        };
+
+        // Invariant: the cell of lo is at the threshold, the cell of hi below it.
+        let (mut lo, mut hi);
+        // There are at least two models, so the sampling set is not empty.
+        let m = guess.clamp(1, n);
+        if size(self, m)? >= self.threshold {
+            lo = m;
+            let mut step = 1;
+            loop {
+                let next = (lo + step).min(n);
+                if next == lo {
+                    return Ok(None);
+                }
+                if size(self, next)? < self.threshold {
+                    hi = next;
+                    break;
+                }
+                lo = next;
+                step *= 2;
+            }
+        } else {
+            hi = m;
+            let mut step = 1;
+            loop {
+                let next = hi.saturating_sub(step);
+                if next == 0 {
+                    lo = 0;
+                    break;
+                }
+                if size(self, next)? >= self.threshold {
+                    lo = next;
+                    break;
+                }
+                hi = next;
+                step *= 2;
+            }
+        }
+
+        while hi - lo > 1 {
+            let mid = (lo + hi) / 2;
+            if size(self, mid)? >= self.threshold {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        Ok(Some((hi, size(self, hi)?)))
+    }
+
+    // Models in the cell, up to the threshold.
+    fn count_cell(&mut self, xors: &[Xor]) -> Result<usize, Error> {
+        self.statistics.cells += 1;
+        Ok(self.cells.enumerate(xors, self.threshold)?.len())
+    }
+}
@@ -0,0 +1,78 @@
+use super::xor::{self, Xor};
+use crate::p::cnf::{CNF, Literal};
+use crate::solvers::sat::limits::Limits;
+use crate::solvers::sat::solver::{IncrementalSolver, Error, Outcome};
+
+// A solver holding the formula, whose models (projected onto the sampling
+// set) are split into cells by XOR hashes. Cells are enumerated with blocking
+// clauses guarded by an activation literal, which is retired afterwards, so
+// one solver serves any number of cells.
+pub(crate) struct Cells<'a, S: IncrementalSolver> {
+    solver: S,
+    sampling_set: &'a [usize],
+    next_var: usize,
+
+    pub solver_calls: u64,
+}
+
+impl<'a, S: IncrementalSolver> Cells<'a, S> {
+    pub fn new(mut solver: S, cnf: &CNF, sampling_set: &'a [usize], limits: &Limits) -> Cells<'a, S> {
+        solver.add_cnf(cnf);
+        solver.set_limits(limits.clone());
+        Cells {
+            solver,
+            sampling_set,
+            next_var: sampling_set.iter().cloned().max().unwrap_or(0).max(cnf.var_count()) + 1,
+            solver_calls: 0,
+        }
+    }
+
+    pub fn sampling_set(&self) -> &'a [usize] {
+        self.sampling_set
+    }
+
+    // Up to bound models of the cell where all the XORs hold, as literals of
+    // the sampling set. The XORs are eliminated first, then encoded; their
+    // fresh variables are functions of the sampling set, so the encodings
+    // stay in the solver without changing the other cells.
+    pub fn enumerate(&mut self, xors: &[Xor], bound: usize) -> Result<Vec<Vec<Literal>>, Error> {
+        let system = match xor::eliminate(xors) {
+            Some(system) => system,
+            None => return Ok(Vec::new()),
+        };
+
+        let mut assumptions = Vec::with_capacity(system.len() + 1);
+        for xor in system.iter() {
+            let (clauses, holds) = xor.encode(&mut self.next_var);
+            for clause in clauses {
+                self.solver.add_clause(clause);
+            }
+            assumptions.push(holds);
+        }
+
+        let activation = Literal::new(self.next_var, false);
+        self.next_var += 1;
+        assumptions.push(activation);
+
+        let mut models = Vec::new();
+        while models.len() < bound {
+            self.solver_calls += 1;
+            match self.solver.solve_with_assumptions(&assumptions)? {
+                Outcome::Sat(_) => {},
+                Outcome::Unsat => break,
+                Outcome::Unknown(reason) => return Err(Error::ResourceExhausted(reason)),
+            }
+
+            let model: Vec<Literal> = self.sampling_set.iter()
+                .map(|v| Literal::new(*v, self.solver.value(Literal::new(*v, false)) != Some(true)))
+                .collect();
+            let mut blocking: Vec<Literal> = model.iter().map(|l| l.neg()).collect();
+            blocking.push(activation.neg());
+            self.solver.add_clause(blocking);
+            models.push(model);
+        }
+
+        self.solver.add_clause(vec![activation.neg()]);
+        Ok(models)
+    }
+}
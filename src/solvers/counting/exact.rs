@@ -0,0 +1,266 @@
+use std::cell::Cell;
+
+use crate::p::bigint::BigUint;
+use crate::p::cnf::{CNF, Literal};
+use crate::p::collections::HashMap;
+use crate::solvers::sat::limits::{Limits, Usage};
+pub use crate::solvers::sat::solver::Error;
+
+// Exact model counter in the style of DPLL: branch on a variable, propagate
+// units and add the counts of both branches. After every step the residual
+// formula is split into components sharing no variables, which are counted
+// separately and multiplied. Component counts are cached, so a component that
+// comes back in another branch is counted once.
+//
+// Counts are over the variables 1..=cnf.var_count().
+// A variable that disappears from the residual formula without being assigned
+// doubles the count.
+pub struct ExactCounter {
+    max_cache_entries: usize,
+
+    statistics: Cell<Statistics>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Statistics {
+    pub decisions: u64,
+    pub components: u64,  // components counted by branching
+    pub cache_hits: u64,
+}
+
+impl Statistics {
+    pub fn entries(&self) -> Vec<(&'static str, u64)> {
+        vec![
+            ("decisions", self.decisions),
+            ("components", self.components),
+            ("cache_hits", self.cache_hits),
+        ]
+    }
+}
+
+struct Counter<'a> {
+    cache: HashMap<Vec<Vec<Literal>>, BigUint>,
+    max_cache_entries: usize,
+
+    limits: &'a Limits,
+    usage: Usage,
+    interrupted: Option<String>,
+
+    statistics: Statistics,
+}
+
+impl Default for ExactCounter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ExactCounter {
+    pub fn new() -> ExactCounter {
+        ExactCounter {
+            max_cache_entries: 1 << 20,
+            statistics: Cell::new(Statistics::default()),
+        }
+    }
+
+    // The cache is flushed when it grows past this many components.
+    pub fn set_max_cache_entries(&mut self, value: usize) {
+        self.max_cache_entries = value;
+    }
+
+    // Statistics of the last call to count.
+    pub fn last_statistics(&self) -> Statistics {
+        self.statistics.get()
+    }
+
+    pub fn count(&self, cnf: &CNF) -> Result<BigUint, Error> {
+        self.count_with_limits(cnf, &Limits::new())
+    }
+
+    // Fails with Error::ResourceExhausted once any of the limits is reached.
+    pub fn count_with_limits(&self, cnf: &CNF, limits: &Limits) -> Result<BigUint, Error> {
+        let variables = cnf.var_count();
+
+        let clauses: Vec<Vec<Literal>> = cnf.clauses().iter()
+            .filter(|c| !c.iter().any(|l| c.contains(&l.neg())))
+            .map(|c| c.to_vec())
+            .collect();
+
+        let mut counter = Counter {
+            cache: HashMap::default(),
+            max_cache_entries: self.max_cache_entries,
+            limits,
+            usage: Usage::default(),
+            interrupted: None,
+            statistics: Statistics::default(),
+        };
+
+        let count = match Counter::simplify(&clauses, Vec::new()) {
+            None => BigUint::zero(),
+            Some((residual, assigned)) => {
+                let free = variables - assigned - Counter::variables(&residual).len();
+                &counter.count_formula(residual) << free
+            },
+        };
+
+        self.statistics.set(counter.statistics);
+        match counter.interrupted {
+            Some(reason) => Err(Error::ResourceExhausted(reason)),
+            None => Ok(count),
+        }
+    }
+}
+
+impl Counter<'_> {
+    fn variables(clauses: &[Vec<Literal>]) -> Vec<usize> {
+        let mut variables: Vec<usize> = clauses.iter().flat_map(|c| c.iter()).map(|l| l.get_var()).collect();
+        variables.sort();
+        variables.dedup();
+        variables
+    }
+
+    // Sets the literals true and propagates units. Returns the residual
+    // clauses and the number of assigned variables, None on conflict.
+    fn simplify(clauses: &[Vec<Literal>], mut pending: Vec<Literal>) -> Option<(Vec<Vec<Literal>>, usize)> {
+        let mut assigned = HashMap::<usize, bool>::default();
+        let mut current: Vec<Vec<Literal>> = clauses.to_vec();
+
+        // The loop below only sees clauses that lose literals, not ones that start empty.
+        if current.iter().any(|c| c.is_empty()) {
+            return None;
+        }
+        for clause in current.iter() {
+            if clause.len() == 1 {
+                pending.push(clause[0]);
+            }
+        }
+
+        while !pending.is_empty() {
+            for l in pending.drain(..) {
+                match assigned.get(&l.get_var()) {
+                    Some(value) if *value == l.is_negative() => return None,
+                    Some(_) => {},
+                    None => {
+                        assigned.insert(l.get_var(), !l.is_negative());
+                    },
+                }
+            }
+
+            let mut next = Vec::with_capacity(current.len());
+            for clause in current.into_iter() {
+                let value = |l: &Literal| assigned.get(&l.get_var()).map(|v| *v != l.is_negative());
+                if clause.iter().any(|l| value(l) == Some(true)) {
+                    continue;
+                }
+
+                let reduced: Vec<Literal> = clause.into_iter().filter(|l| value(l).is_none()).collect();
+                match reduced.len() {
+                    0 => return None,
+                    1 => pending.push(reduced[0]),
+                    _ => {},
+                }
+                next.push(reduced);
+            }
+            current = next;
+        }
+
+        Some((current, assigned.len()))
+    }
+
+    // Splits the clauses into groups sharing no variables.
+    fn components(clauses: Vec<Vec<Literal>>) -> Vec<Vec<Vec<Literal>>> {
+        let variables = Self::variables(&clauses);
+        let position: HashMap<usize, usize> = variables.iter().enumerate().map(|(i, v)| (*v, i)).collect();
+
+        let mut parent: Vec<usize> = (0..variables.len()).collect();
+        fn find(parent: &mut [usize], mut x: usize) -> usize {
+            while parent[x] != x {
+                parent[x] = parent[parent[x]];
+                x = parent[x];
+            }
+            x
+        }
+
+        for clause in clauses.iter() {
+            let first = find(&mut parent, position[&clause[0].get_var()]);
+            for l in clause.iter().skip(1) {
+                let other = find(&mut parent, position[&l.get_var()]);
+                parent[other] = first;
+            }
+        }
+
+        let mut groups = HashMap::<usize, usize>::default();
+        let mut components: Vec<Vec<Vec<Literal>>> = Vec::new();
+        for clause in clauses.into_iter() {
+            let root = find(&mut parent, position[&clause[0].get_var()]);
+            let group = *groups.entry(root).or_insert_with(|| {
+                components.push(Vec::new());
+                components.len() - 1
+            });
+            components[group].push(clause);
+        }
+        components
+    }
+
+    // Models of the clauses over their own variables.
+    fn count_formula(&mut self, clauses: Vec<Vec<Literal>>) -> BigUint {
+        let mut count = BigUint::one();
+        for component in Self::components(clauses) {
+            if count.is_zero() {
+                break;
+            }
+            count = &count * &self.count_component(component);
+        }
+        count
+    }
+
+    fn count_component(&mut self, mut clauses: Vec<Vec<Literal>>) -> BigUint {
+        clauses.sort();
+        if let Some(count) = self.cache.get(&clauses) {
+            self.statistics.cache_hits += 1;
+            return count.clone();
+        }
+
+        if self.interrupted.is_some() {
+            return BigUint::zero();
+        }
+        self.usage.decisions += 1;
+        self.statistics.decisions += 1;
+        if let Some(reason) = self.limits.check(&mut self.usage) {
+            self.interrupted = Some(reason);
+            return BigUint::zero();
+        }
+
+        self.statistics.components += 1;
+        let variables = Self::variables(&clauses);
+        let var = Self::pick_variable(&clauses);
+
+        let mut count = BigUint::zero();
+        for sign in [false, true] {
+            if let Some((residual, assigned)) = Self::simplify(&clauses, vec![Literal::new(var, sign)]) {
+                let free = variables.len() - assigned - Self::variables(&residual).len();
+                count = &count + &(&self.count_formula(residual) << free);
+            }
+        }
+
+        if self.interrupted.is_none() {
+            if self.cache.len() >= self.max_cache_entries {
+                self.cache.clear();
+            }
+            self.cache.insert(clauses, count.clone());
+        }
+        count
+    }
+
+    // The variable with the most occurrences, the smallest one on ties.
+    fn pick_variable(clauses: &[Vec<Literal>]) -> usize {
+        let mut occurrences = HashMap::<usize, usize>::default();
+        for l in clauses.iter().flat_map(|c| c.iter()) {
+            *occurrences.entry(l.get_var()).or_insert(0) += 1;
+        }
+        occurrences.into_iter()
+            .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(&a.0)))
+            .unwrap()
+            .0
+    }
+}
@@ -0,0 +1,154 @@
+use rand::Rng;
+
+use crate::p::cnf::{CNF, Literal};
+use crate::p::collections::HashMap;
+
+// A long XOR is cut into links of this many variables, chained through fresh
+// variables. A link also holds the fresh variables of its own and of the
+// previous link, and takes 2^(k-1) clauses for its k variables.
+const LINK: usize = 3;
+
+// Parity constraint x1 ^ x2 ^ ... ^ xk = parity. The empty XOR is false,
+// so it holds exactly when the parity is false.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Xor {
+    pub variables: Vec<usize>,
+    pub parity: bool,
+}
+
+impl Xor {
+    pub fn new(variables: Vec<usize>, parity: bool) -> Xor {
+        Xor { variables, parity }
+    }
+
+    // Every variable is taken with probability 1/2, the parity is uniform.
+    // These XORs form a 3-wise independent hash family.
+    pub fn random<R: Rng>(variables: &[usize], rng: &mut R) -> Xor {
+        Xor {
+            variables: variables.iter().cloned().filter(|_| rng.gen::<bool>()).collect(),
+            parity: rng.gen(),
+        }
+    }
+
+    pub fn eval(&self, model: &[Literal]) -> bool {
+        let mut value = false;
+        for v in self.variables.iter() {
+            value ^= model.contains(&Literal::new(*v, false));
+        }
+        value == self.parity
+    }
+
+    // Clauses defining fresh variables, numbered from next_var on, the last
+    // of which equals the XOR of the variables. Returns them together with
+    // the literal that is true exactly when the constraint holds.
+    // The fresh variables are functions of the XOR variables, so the clauses
+    // never change the number of models over the original variables.
+    pub fn encode(&self, next_var: &mut usize) -> (Vec<Vec<Literal>>, Literal) {
+        let mut clauses = Vec::new();
+        let mut fresh = || {
+            *next_var += 1;
+            *next_var - 1
+        };
+
+        let mut result = fresh();
+        if self.variables.is_empty() {
+            clauses.push(vec![Literal::new(result, true)]);
+        }
+
+        let mut carry: Option<usize> = None;
+        for link in self.variables.chunks(LINK) {
+            let mut variables: Vec<usize> = carry.into_iter().chain(link.iter().cloned()).collect();
+            if carry.is_some() {
+                result = fresh();
+            }
+            variables.push(result);
+            Self::add_even(&variables, &mut clauses);
+            carry = Some(result);
+        }
+
+        (clauses, Literal::new(result, !self.parity))
+    }
+
+    // Adds the constraint to the CNF, the fresh variables follow cnf.var_count().
+    pub fn add_to(&self, cnf: &mut CNF) {
+        let mut next_var = cnf.var_count() + 1;
+        let (clauses, holds) = self.encode(&mut next_var);
+        for clause in clauses {
+            cnf.add_clause(clause);
+        }
+        cnf.add_clause(vec![holds]);
+    }
+
+    // Direct encoding of x1 ^ ... ^ xk = 0: every assignment with odd
+    // parity is excluded by the clause it falsifies.
+    fn add_even(variables: &[usize], clauses: &mut Vec<Vec<Literal>>) {
+        for mask in 0..(1usize << variables.len()) {
+            if mask.count_ones() % 2 == 1 {
+                let clause = variables.iter().enumerate()
+                    .map(|(i, v)| Literal::new(*v, mask >> i & 1 == 1))
+                    .collect();
+                clauses.push(clause);
+            }
+        }
+    }
+}
+
+// Gauss-Jordan elimination: an equivalent system in which every XOR has a
+// pivot variable that occurs in no other XOR. Dependent XORs are dropped,
+// None if the system has no solution.
+//
+// Chained XORs are hard for resolution, so a solver given the raw system can
+// take exponential time to find a conflict that elimination shows at once.
+// With private pivots, a conflict stays within a single XOR.
+pub fn eliminate(xors: &[Xor]) -> Option<Vec<Xor>> {
+    let mut variables: Vec<usize> = xors.iter().flat_map(|x| x.variables.iter().cloned()).collect();
+    variables.sort();
+    variables.dedup();
+    let position: HashMap<usize, usize> = variables.iter().enumerate().map(|(i, v)| (*v, i)).collect();
+
+    let words = variables.len().div_ceil(64);
+    let mut rows: Vec<(Vec<u64>, bool)> = xors.iter()
+        .map(|x| {
+            let mut bits = vec![0u64; words];
+            for v in x.variables.iter() {
+                let i = position[v];
+                bits[i / 64] ^= 1 << (i % 64);
+            }
+            (bits, x.parity)
+        })
+        .collect();
+
+    let mut rank = 0;
+    for column in 0..variables.len() {
+        let (word, bit) = (column / 64, 1u64 << (column % 64));
+        let pivot = match (rank..rows.len()).find(|&i| rows[i].0[word] & bit != 0) {
+            Some(pivot) => pivot,
+            None => continue,
+        };
+        rows.swap(rank, pivot);
+
+        let (pivot_bits, pivot_parity) = rows[rank].clone();
+        for (i, row) in rows.iter_mut().enumerate() {
+            if i != rank && row.0[word] & bit != 0 {
+                for (a, b) in row.0.iter_mut().zip(pivot_bits.iter()) {
+                    *a ^= b;
+                }
+                row.1 ^= pivot_parity;
+            }
+        }
+        rank += 1;
+    }
+
+    if rows[rank..].iter().any(|row| row.1) {
+        return None;
+    }
+    rows.truncate(rank);
+
+    let system = rows.into_iter()
+        .map(|(bits, parity)| Xor {
+            variables: (0..variables.len()).filter(|i| bits[i / 64] >> (i % 64) & 1 == 1).map(|i| variables[i]).collect(),
+            parity,
+        })
+        .collect();
+    Some(system)
+}
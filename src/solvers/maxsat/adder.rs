@@ -0,0 +1,120 @@
+use crate::p::cnf::Literal;
+
+// Pseudo-Boolean encoding with adders (Warners; Eén and Sörensson): full and
+// half adders sum the weighted literals bit by bit into a binary number, which
+// a comparator then bounds. Its size grows with the logarithm of the weights,
+// unlike the totalizer, whose size grows with the number of reachable sums.
+pub struct Adder {
+    bits: Vec<Option<Literal>>,  // the sum, least significant bit first, None for a constant 0
+}
+
+impl Adder {
+    // Appends the clauses to `clauses`, fresh variables are numbered from next_var on.
+    pub fn new(inputs: &[(Literal, u64)], next_var: &mut usize, clauses: &mut Vec<Vec<Literal>>) -> Adder {
+        let mut fresh = || {
+            *next_var += 1;
+            Literal::new(*next_var - 1, false)
+        };
+
+        // buckets[b] holds the literals that add 2^b to the sum.
+        let mut buckets: Vec<Vec<Literal>> = Vec::new();
+        for (l, w) in inputs.iter() {
+            for b in 0..64 {
+                if w >> b & 1 == 1 {
+                    if buckets.len() <= b {
+                        buckets.resize(b + 1, Vec::new());
+                    }
+                    buckets[b].push(*l);
+                }
+            }
+        }
+
+        let mut bits = Vec::new();
+        let mut b = 0;
+        while b < buckets.len() {
+            while buckets[b].len() >= 2 {
+                let x = buckets[b].pop().unwrap();
+                let y = buckets[b].pop().unwrap();
+                let z = if buckets[b].len() >= 2 { buckets[b].pop() } else { None };
+                let (sum, carry) = (fresh(), fresh());
+                match z {
+                    Some(z) => Self::full_adder(x, y, z, sum, carry, clauses),
+                    None => Self::half_adder(x, y, sum, carry, clauses),
+                }
+                buckets[b].insert(0, sum);
+                if buckets.len() <= b + 1 {
+                    buckets.push(Vec::new());
+                }
+                buckets[b + 1].push(carry);
+            }
+            bits.push(buckets[b].pop());
+            b += 1;
+        }
+        Adder { bits }
+    }
+
+    // sum = x ^ y ^ z, carry = at least two of x, y, z. Every assignment of
+    // the inputs is excluded by the clause it falsifies, unless the outputs
+    // take their values.
+    fn full_adder(x: Literal, y: Literal, z: Literal, sum: Literal, carry: Literal, clauses: &mut Vec<Vec<Literal>>) {
+        for mask in 0..8u32 {
+            let inputs: Vec<Literal> = [x, y, z].iter().enumerate()
+                .map(|(i, l)| if mask >> i & 1 == 1 { l.neg() } else { *l })
+                .collect();
+            let ones = mask.count_ones();
+
+            let mut clause = inputs.clone();
+            clause.push(if ones % 2 == 1 { sum } else { sum.neg() });
+            clauses.push(clause);
+
+            let mut clause = inputs;
+            clause.push(if ones >= 2 { carry } else { carry.neg() });
+            clauses.push(clause);
+        }
+    }
+
+    // sum = x ^ y, carry = x & y
+    fn half_adder(x: Literal, y: Literal, sum: Literal, carry: Literal, clauses: &mut Vec<Vec<Literal>>) {
+        clauses.push(vec![x.neg(), y.neg(), sum.neg()]);
+        clauses.push(vec![x, y, sum.neg()]);
+        clauses.push(vec![x.neg(), y, sum]);
+        clauses.push(vec![x, y.neg(), sum]);
+        clauses.push(vec![x.neg(), y.neg(), carry]);
+        clauses.push(vec![x, carry.neg()]);
+        clauses.push(vec![y, carry.neg()]);
+    }
+
+    // Clauses bounding the sum by k. For every 0 bit of k, the sum may not
+    // have a 1 there while matching all the 1 bits of k above it.
+    pub fn at_most(&self, k: u64) -> Vec<Vec<Literal>> {
+        if self.bits.len() < 64 && k >> self.bits.len() != 0 {
+            return Vec::new();
+        }
+        let bit = |i: usize| i < 64 && k >> i & 1 == 1;
+
+        let mut clauses = Vec::new();
+        for i in 0..self.bits.len() {
+            let l = match self.bits[i] {
+                Some(l) if !bit(i) => l,
+                _ => continue,
+            };
+            let mut clause = vec![l.neg()];
+            for j in i + 1..self.bits.len() {
+                if bit(j) {
+                    match self.bits[j] {
+                        Some(o) => clause.push(o.neg()),
+                        // The sum has a 0 where k has a 1, it is already below k.
+                        None => {
+                            clause.clear();
+                            break;
+                        },
+                    }
+                }
+            }
+            if !clause.is_empty() {
+                clauses.push(clause);
+            }
+        }
+        clauses
+    }
+}
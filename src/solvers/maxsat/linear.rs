@@ -0,0 +1,149 @@
+use std::cell::Cell;
+
+use super::solver::{self, MaxSatSolver, Outcome, Solution, Limits, Error};
+use super::adder::Adder;
+use super::totalizer::Totalizer;
+use crate::p::cnf::Literal;
+use crate::p::wcnf::WCNF;
+use crate::solvers::sat::cdcl::IncrementalCDCL;
+use crate::solvers::sat::solver::{self as sat, IncrementalSolver};
+
+// Linear SAT-UNSAT search: every soft clause gets a relaxation literal that
+// is true when the clause may be falsified. After each model of cost c, a
+// pseudo-Boolean encoding over the weighted relaxation literals bounds the
+// cost below c; the last model before UNSAT is optimal. Every model is an
+// upper bound, so a stopped search still reports the best one. With equal
+// weights the bound is a totalizer, otherwise an adder, whose size does not
+// grow with the range of the weights.
+pub struct LinearSearch {
+    statistics: Cell<Statistics>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Statistics {
+    pub sat_calls: u64,
+    pub improvements: u64,  // models found, each cheaper than the one before
+}
+
+impl Statistics {
+    pub fn entries(&self) -> Vec<(&'static str, u64)> {
+        vec![
+            ("sat_calls", self.sat_calls),
+            ("improvements", self.improvements),
+        ]
+    }
+}
+
+impl Default for LinearSearch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MaxSatSolver for LinearSearch {
+    fn solve_with_limits(&self, wcnf: &WCNF, limits: &Limits) -> Result<Outcome, Error> {
+        let mut statistics = Statistics::default();
+        let outcome = Self::search(wcnf, limits, &mut statistics);
+        self.statistics.set(statistics);
+        outcome
+    }
+
+    fn statistics(&self) -> Vec<(&'static str, u64)> {
+        self.last_statistics().entries()
+    }
+}
+
+impl LinearSearch {
+    pub fn new() -> LinearSearch {
+        LinearSearch {
+            statistics: Cell::new(Statistics::default()),
+        }
+    }
+
+    // Statistics of the last call to solve.
+    pub fn last_statistics(&self) -> Statistics {
+        self.statistics.get()
+    }
+
+    fn search(wcnf: &WCNF, limits: &Limits, statistics: &mut Statistics) -> Result<Outcome, Error> {
+        let mut solver = IncrementalCDCL::new();
+        solver.set_limits(limits.clone());
+        for clause in wcnf.hard().iter() {
+            solver.add_clause(clause.clone());
+        }
+
+        let mut next_var = wcnf.var_count() + 1;
+        let mut relaxation: Vec<(Literal, u64)> = Vec::new();
+        for (clause, weight) in wcnf.soft().iter() {
+            // A unit soft clause is falsified exactly when its negation holds.
+            let r = if clause.len() == 1 {
+                clause[0].neg()
+            } else {
+                let r = Literal::new(next_var, false);
+                next_var += 1;
+                let mut relaxed = clause.clone();
+                relaxed.push(r);
+                solver.add_clause(relaxed);
+                r
+            };
+            relaxation.push((r, *weight));
+        }
+
+        let uniform = relaxation.windows(2).all(|pair| pair[0].1 == pair[1].1);
+        let mut best: Option<Solution> = None;
+        let mut bound: Option<Bound> = None;
+        loop {
+            statistics.sat_calls += 1;
+            match solver.solve_with_assumptions(&[])? {
+                sat::Outcome::Sat(_) => {},
+                sat::Outcome::Unsat => {
+                    return Ok(match best {
+                        Some(solution) => Outcome::Optimum(solution),
+                        None => Outcome::Unsat,
+                    });
+                },
+                sat::Outcome::Unknown(reason) => return Ok(Outcome::Unknown { reason, best }),
+            }
+
+            let model = solver::model(&solver, wcnf);
+            let cost = wcnf.cost(&model).expect("a model of the hard clauses");
+            statistics.improvements += 1;
+            best = Some(Solution { model, cost });
+            if cost == 0 {
+                return Ok(Outcome::Optimum(best.unwrap()));
+            }
+
+            let bound = bound.get_or_insert_with(|| {
+                let mut clauses = Vec::new();
+                let bound = if uniform {
+                    // Counts the falsified clauses; the first cost is the largest one.
+                    let w = relaxation[0].1;
+                    let inputs: Vec<(Literal, u64)> = relaxation.iter().map(|(r, _)| (*r, 1)).collect();
+                    Bound::Totalizer(w, Totalizer::new(&inputs, cost / w, &mut next_var, &mut clauses))
+                } else {
+                    Bound::Adder(Adder::new(&relaxation, &mut next_var, &mut clauses))
+                };
+                for clause in clauses {
+                    solver.add_clause(clause);
+                }
+                bound
+            });
+            match bound {
+                Bound::Totalizer(w, totalizer) => match totalizer.at_least(cost / *w) {
+                    Some(l) => solver.add_clause(vec![l.neg()]),
+                    None => return Ok(Outcome::Optimum(best.unwrap())),
+                },
+                Bound::Adder(adder) => {
+                    for clause in adder.at_most(cost - 1) {
+                        solver.add_clause(clause);
+                    }
+                },
+            }
+        }
+    }
+}
+
+enum Bound {
+    Totalizer(u64, Totalizer),  // over unit weights, with the common weight
+    Adder(Adder),
+}
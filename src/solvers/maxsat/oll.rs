@@ -0,0 +1,188 @@
+use std::cell::Cell;
+
+use super::solver::{self, MaxSatSolver, Outcome, Solution, Limits, Error};
+use super::totalizer::Totalizer;
+use crate::p::cnf::Literal;
+use crate::p::collections::HashMap;
+use crate::p::wcnf::WCNF;
+use crate::solvers::sat::cdcl::IncrementalCDCL;
+use crate::solvers::sat::solver::{self as sat, IncrementalSolver};
+
+// Times a core is solved again to shrink it.
+const TRIM_ROUNDS: usize = 3;
+
+// Core-guided OLL search as in RC2 (Ignatiev, Morgado, Marques-Silva).
+// Every soft clause is assumed to hold. An unsatisfiable core of assumptions
+// raises the lower bound by its smallest weight w, which is taken off every
+// assumption in the core. A totalizer over the core then counts its
+// falsified clauses: one is unavoidable, and assuming at most one more is
+// falsified becomes a new soft assumption of weight w. When such an
+// assumption is in a core in turn, the bound on its totalizer goes up by one.
+// The first model is optimal.
+pub struct OLL {
+    statistics: Cell<Statistics>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Statistics {
+    pub sat_calls: u64,
+    pub cores: u64,
+    pub totalizers: u64,
+}
+
+impl Statistics {
+    pub fn entries(&self) -> Vec<(&'static str, u64)> {
+        vec![
+            ("sat_calls", self.sat_calls),
+            ("cores", self.cores),
+            ("totalizers", self.totalizers),
+        ]
+    }
+}
+
+impl Default for OLL {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MaxSatSolver for OLL {
+    fn solve_with_limits(&self, wcnf: &WCNF, limits: &Limits) -> Result<Outcome, Error> {
+        let mut statistics = Statistics::default();
+        let outcome = Self::search(wcnf, limits, &mut statistics);
+        self.statistics.set(statistics);
+        outcome
+    }
+
+    fn statistics(&self) -> Vec<(&'static str, u64)> {
+        self.last_statistics().entries()
+    }
+}
+
+impl OLL {
+    pub fn new() -> OLL {
+        OLL {
+            statistics: Cell::new(Statistics::default()),
+        }
+    }
+
+    // Statistics of the last call to solve.
+    pub fn last_statistics(&self) -> Statistics {
+        self.statistics.get()
+    }
+
+    fn search(wcnf: &WCNF, limits: &Limits, statistics: &mut Statistics) -> Result<Outcome, Error> {
+        let mut solver = IncrementalCDCL::new();
+        solver.set_limits(limits.clone());
+        for clause in wcnf.hard().iter() {
+            solver.add_clause(clause.clone());
+        }
+
+        // Assumptions in the order they were introduced, with their remaining weights.
+        let mut order: Vec<Literal> = Vec::new();
+        let mut weight: HashMap<Literal, u64> = HashMap::default();
+        let mut next_var = wcnf.var_count() + 1;
+        let mut lower_bound = 0;
+        for (clause, w) in wcnf.soft().iter() {
+            match clause.len() {
+                0 => lower_bound += w,
+                1 => Self::assume(&mut order, &mut weight, clause[0], *w),
+                _ => {
+                    let a = Literal::new(next_var, false);
+                    next_var += 1;
+                    let mut guarded = clause.clone();
+                    guarded.push(a.neg());
+                    solver.add_clause(guarded);
+                    Self::assume(&mut order, &mut weight, a, *w);
+                },
+            }
+        }
+
+        // An assumption ¬o is the bound "fewer than k inputs of totalizer t
+        // are true", where o = totalizers[t].at_least(k).
+        let mut totalizers: Vec<Totalizer> = Vec::new();
+        let mut bounds: HashMap<Literal, (usize, u64)> = HashMap::default();
+
+        loop {
+            let assumptions: Vec<Literal> = order.iter().cloned().filter(|l| weight[l] > 0).collect();
+            statistics.sat_calls += 1;
+            match solver.solve_with_assumptions(&assumptions)? {
+                sat::Outcome::Sat(_) => {
+                    let model = solver::model(&solver, wcnf);
+                    let cost = wcnf.cost(&model).expect("a model of the hard clauses");
+                    debug_assert_eq!(cost, lower_bound);
+                    return Ok(Outcome::Optimum(Solution { model, cost }));
+                },
+                sat::Outcome::Unsat => {},
+                sat::Outcome::Unknown(reason) => return Ok(Outcome::Unknown { reason, best: None }),
+            }
+
+            let mut core = solver.failed_assumptions();
+            core.sort();
+            core.dedup();
+            if core.is_empty() {
+                return Ok(Outcome::Unsat);
+            }
+
+            // Solving again under the core alone often gives a smaller one.
+            for _ in 0..TRIM_ROUNDS {
+                if core.len() <= 1 {
+                    break;
+                }
+                statistics.sat_calls += 1;
+                match solver.solve_with_assumptions(&core)? {
+                    sat::Outcome::Unsat => {},
+                    sat::Outcome::Sat(_) => break,
+                    sat::Outcome::Unknown(reason) => return Ok(Outcome::Unknown { reason, best: None }),
+                }
+                let mut trimmed = solver.failed_assumptions();
+                trimmed.sort();
+                trimmed.dedup();
+                if trimmed.len() >= core.len() {
+                    break;
+                }
+                core = trimmed;
+            }
+            statistics.cores += 1;
+
+            let w = core.iter().map(|l| weight[l]).min().unwrap();
+            lower_bound += w;
+            for l in core.iter() {
+                *weight.get_mut(l).unwrap() -= w;
+            }
+
+            // Relaxes the bounds in the core by one.
+            for l in core.iter() {
+                if let Some(&(t, k)) = bounds.get(l) {
+                    if let Some(o) = totalizers[t].at_least(k + 1) {
+                        bounds.insert(o.neg(), (t, k + 1));
+                        Self::assume(&mut order, &mut weight, o.neg(), w);
+                    }
+                }
+            }
+
+            if core.len() > 1 {
+                let inputs: Vec<(Literal, u64)> = core.iter().map(|l| (l.neg(), 1)).collect();
+                let mut clauses = Vec::new();
+                let totalizer = Totalizer::new(&inputs, core.len() as u64, &mut next_var, &mut clauses);
+                for clause in clauses {
+                    solver.add_clause(clause);
+                }
+                statistics.totalizers += 1;
+
+                let o = totalizer.at_least(2).unwrap();
+                totalizers.push(totalizer);
+                bounds.insert(o.neg(), (totalizers.len() - 1, 2));
+                Self::assume(&mut order, &mut weight, o.neg(), w);
+            }
+        }
+    }
+
+    // Adds weight w to the assumption l, appending it to the order when new.
+    fn assume(order: &mut Vec<Literal>, weight: &mut HashMap<Literal, u64>, l: Literal, w: u64) {
+        if !weight.contains_key(&l) {
+            order.push(l);
+        }
+        *weight.entry(l).or_insert(0) += w;
+    }
+}
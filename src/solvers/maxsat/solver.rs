@@ -0,0 +1,58 @@
+use crate::p::assignment::Assignment;
+use crate::p::cnf::Literal;
+use crate::p::wcnf::WCNF;
+use crate::solvers::sat::solver::IncrementalSolver;
+pub use crate::solvers::sat::limits::Limits;
+pub use crate::solvers::sat::solver::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Solution {
+    pub model: Assignment,  // a value for every variable 1..=wcnf.var_count()
+    pub cost: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Optimum(Solution),
+    // The hard clauses are unsatisfiable.
+    Unsat,
+    // Stopped by the limits, with the best solution found so far, if any.
+    Unknown { reason: String, best: Option<Solution> },
+}
+
+impl Outcome {
+    pub fn is_optimum(&self) -> bool {
+        matches!(self, Outcome::Optimum(_))
+    }
+
+    // The optimum, or the best solution found before the solver gave up.
+    pub fn solution(&self) -> Option<&Solution> {
+        match self {
+            Outcome::Optimum(solution) => Some(solution),
+            Outcome::Unknown { best, .. } => best.as_ref(),
+            Outcome::Unsat => None,
+        }
+    }
+}
+
+pub trait MaxSatSolver {
+    // Minimizes the cost, answering Outcome::Unknown as soon as any of the limits is reached.
+    fn solve_with_limits(&self, wcnf: &WCNF, limits: &Limits) -> Result<Outcome, Error>;
+
+    fn solve(&self, wcnf: &WCNF) -> Result<Outcome, Error> {
+        self.solve_with_limits(wcnf, &Limits::new())
+    }
+
+    // Counters of the last call as (name, value) pairs, empty if the solver keeps none.
+    fn statistics(&self) -> Vec<(&'static str, u64)> {
+        Vec::new()
+    }
+}
+
+// Model of the last SAT call over the variables of the instance, unassigned ones taken as false.
+pub(crate) fn model<S: IncrementalSolver>(solver: &S, wcnf: &WCNF) -> Assignment {
+    let values = (0..=wcnf.var_count())
+        .map(|v| Some(v > 0 && solver.value(Literal::new(v, false)) == Some(true)))
+        .collect();
+    Assignment::from_values(values)
+}
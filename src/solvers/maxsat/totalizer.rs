@@ -0,0 +1,76 @@
+use crate::p::cnf::Literal;
+
+// Generalized totalizer (Joshi, Martins, Manquinho): a binary tree of adders
+// over weighted literals. Every node has an output per sum that the literals
+// below it can reach, sums from cap on being merged into cap. An output is
+// forced true once the true literals below weigh at least its sum. With unit
+// weights this is the totalizer of Bailleux and Boufkhad, used for
+// cardinality constraints.
+pub struct Totalizer {
+    outputs: Vec<(u64, Literal)>,  // root outputs by increasing sum
+}
+
+impl Totalizer {
+    // Appends the clauses to `clauses`, fresh variables are numbered from next_var on.
+    pub fn new(inputs: &[(Literal, u64)], cap: u64, next_var: &mut usize, clauses: &mut Vec<Vec<Literal>>) -> Totalizer {
+        if inputs.is_empty() {
+            return Totalizer { outputs: Vec::new() };
+        }
+
+        let outputs = Self::build(inputs, cap, next_var, clauses);
+
+        // A node forces the output of its exact (capped) sum, so the root also
+        // gets the outputs of smaller sums.
+        for pair in outputs.windows(2) {
+            clauses.push(vec![pair[1].1.neg(), pair[0].1]);
+        }
+        Totalizer { outputs }
+    }
+
+    fn build(inputs: &[(Literal, u64)], cap: u64, next_var: &mut usize, clauses: &mut Vec<Vec<Literal>>) -> Vec<(u64, Literal)> {
+        if inputs.len() == 1 {
+            return vec![(inputs[0].1.min(cap), inputs[0].0)];
+        }
+
+        let (left, right) = inputs.split_at(inputs.len() / 2);
+        let left = Self::build(left, cap, next_var, clauses);
+        let right = Self::build(right, cap, next_var, clauses);
+
+        let mut sums: Vec<u64> = left.iter().chain(right.iter()).map(|(s, _)| *s).collect();
+        for (a, _) in left.iter() {
+            for (b, _) in right.iter() {
+                sums.push((a + b).min(cap));
+            }
+        }
+        sums.sort();
+        sums.dedup();
+
+        let outputs: Vec<(u64, Literal)> = sums.into_iter()
+            .map(|s| {
+                *next_var += 1;
+                (s, Literal::new(*next_var - 1, false))
+            })
+            .collect();
+        let output = |s: u64| outputs[outputs.binary_search_by_key(&s, |(sum, _)| *sum).unwrap()].1;
+
+        for (s, l) in left.iter().chain(right.iter()) {
+            clauses.push(vec![l.neg(), output(*s)]);
+        }
+        for (a, la) in left.iter() {
+            for (b, lb) in right.iter() {
+                clauses.push(vec![la.neg(), lb.neg(), output((a + b).min(cap))]);
+            }
+        }
+        outputs
+    }
+
+    // Literal that is true whenever the true inputs weigh at least value, for
+    // 1 <= value <= cap. None if no set of inputs weighs that much.
+    pub fn at_least(&self, value: u64) -> Option<Literal> {
+        self.outputs.iter().find(|(s, _)| *s >= value).map(|(_, l)| *l)
+    }
+
+    pub fn outputs(&self) -> &Vec<(u64, Literal)> {
+        &self.outputs
+    }
+}
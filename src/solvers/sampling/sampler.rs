@@ -0,0 +1,323 @@
+use std::cell::Cell;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use crate::p::assignment::Assignment;
+use crate::p::cnf::{CNF, Literal};
+use crate::solvers::counting::approx::ApproxCounter;
+use crate::solvers::counting::cell::Cells;
+use crate::solvers::counting::xor::Xor;
+use crate::solvers::sat::cdcl::IncrementalCDCL;
+use crate::solvers::sat::limits::Limits;
+pub use crate::solvers::sat::solver::{IncrementalSolver, Error, Outcome};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Mode {
+    // Hashing-based sampling after UniGen (Chakraborty, Fremont, Meel,
+    // Seshia, Vardi): every model is drawn with a probability within a
+    // factor of (1 + epsilon) of uniform.
+    #[default]
+    UniGen,
+    // Sets the sampling variables one by one in random order to random
+    // values, as long as the formula stays satisfiable. Cheap, but without
+    // any guarantee: models in denser parts of the space are favoured.
+    Heuristic,
+}
+
+// Draws models of a CNF, projected onto the sampling set.
+pub struct Sampler {
+    mode: Mode,
+    epsilon: f64,
+    sampling_set: Option<Vec<usize>>,
+    seed: Option<u64>,
+    limits: Limits,
+
+    statistics: Cell<Statistics>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Statistics {
+    pub samples: u64,
+    pub failed_attempts: u64,  // hashes whose cells were all too small or too large
+    pub xors: u64,
+    pub cells: u64,
+    pub solver_calls: u64,
+}
+
+impl Statistics {
+    pub fn entries(&self) -> Vec<(&'static str, u64)> {
+        vec![
+            ("samples", self.samples),
+            ("failed_attempts", self.failed_attempts),
+            ("xors", self.xors),
+            ("cells", self.cells),
+            ("solver_calls", self.solver_calls),
+        ]
+    }
+}
+
+impl Default for Sampler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sampler {
+    // UniGen gives up after this many attempts per requested sample. Each
+    // one succeeds with probability at least 0.62, so with the right
+    // estimate the cap is practically never reached.
+    const MAX_ATTEMPTS_PER_SAMPLE: usize = 10;
+
+    pub fn new() -> Sampler {
+        Sampler {
+            mode: Mode::default(),
+            epsilon: 16.0,
+            sampling_set: None,
+            seed: None,
+            limits: Limits::new(),
+            statistics: Cell::new(Statistics::default()),
+        }
+    }
+
+    pub fn set_mode(&mut self, value: Mode) {
+        self.mode = value;
+    }
+
+    // Tolerance of UniGen, which needs epsilon > 6.84. Larger values give
+    // larger cells, so fewer failed attempts.
+    pub fn set_epsilon(&mut self, value: f64) {
+        self.epsilon = value;
+    }
+
+    // Variables the samples are drawn over. Every variable should be a
+    // function of these (an independent support), otherwise the samples
+    // are uniform over the projections of the models onto them.
+    pub fn set_sampling_set(&mut self, value: Vec<usize>) {
+        self.sampling_set = Some(value);
+    }
+
+    pub fn set_seed(&mut self, value: u64) {
+        self.seed = Some(value);
+    }
+
+    // Applied to every solver call, a deadline or cancellation covers all the samples.
+    pub fn set_limits(&mut self, value: Limits) {
+        self.limits = value;
+    }
+
+    // Statistics of the last call to sample.
+    pub fn last_statistics(&self) -> Statistics {
+        self.statistics.get()
+    }
+
+    // Returns count samples, each a model restricted to the sampling set,
+    // the other variables being left unassigned. Samples are drawn
+    // independently and may repeat. No samples if the formula is unsatisfiable.
+    pub fn sample(&self, cnf: &CNF, count: usize) -> Result<Vec<Assignment>, Error> {
+        self.sample_with(IncrementalCDCL::new, cnf, count)
+    }
+
+    pub fn sample_with<S: IncrementalSolver, F: Fn() -> S>(&self, new_solver: F, cnf: &CNF, count: usize) -> Result<Vec<Assignment>, Error> {
+        if self.mode == Mode::UniGen && (self.epsilon.is_nan() || self.epsilon <= 6.84) {
+            return Err(Error::InvalidInput(format!("UniGen needs epsilon > 6.84, got {}", self.epsilon)));
+        }
+
+        let sampling_set: Vec<usize> = match &self.sampling_set {
+            Some(sampling_set) => {
+                let mut variables = sampling_set.clone();
+                variables.sort();
+                variables.dedup();
+                variables
+            },
+            None => (1..=cnf.var_count()).collect(),
+        };
+
+        let mut rng = match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        let mut statistics = Statistics::default();
+
+        let samples = match self.mode {
+            Mode::UniGen => self.unigen(&new_solver, cnf, &sampling_set, count, &mut rng, &mut statistics),
+            Mode::Heuristic => self.heuristic(new_solver(), cnf, &sampling_set, count, &mut rng, &mut statistics),
+        };
+        if let Ok(samples) = samples.as_ref() {
+            statistics.samples = samples.len() as u64;
+        }
+        self.statistics.set(statistics);
+
+        let var_count = sampling_set.iter().cloned().max().unwrap_or(0).max(cnf.var_count());
+        samples.map(|samples| {
+            samples.into_iter()
+                .map(|sample| {
+                    let mut values = vec![None; var_count + 1];
+                    for l in sample.iter() {
+                        values[l.get_var()] = Some(!l.is_negative());
+                    }
+                    Assignment::from_values(values)
+                })
+                .collect()
+        })
+    }
+
+    // The kappa with epsilon = (1 + kappa)(7.44 + 0.392 / (1 - kappa)^2) - 1,
+    // and the pivot 4.03 (1 + 1/kappa)^2 the cell sizes are centered on.
+    fn kappa_pivot(&self) -> (f64, usize) {
+        let error = |kappa: f64| (1.0 + kappa) * (7.44 + 0.392 / (1.0 - kappa).powi(2)) - 1.0;
+        let (mut lo, mut hi) = (0.0, 1.0);
+        for _ in 0..100 {
+            let mid = (lo + hi) / 2.0;
+            if error(mid) < self.epsilon {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        (lo, (4.03 * (1.0 + 1.0 / lo).powi(2)).ceil() as usize)
+    }
+
+    // Estimates the count, then for every sample tries hashes with q - 3 to q
+    // XORs, q chosen so that cells are expected around the pivot. The first
+    // cell with a size in [lo, hi] gives a sample drawn uniformly from it.
+    fn unigen<S: IncrementalSolver, F: Fn() -> S>(
+        &self, new_solver: &F, cnf: &CNF, sampling_set: &[usize], count: usize,
+        rng: &mut StdRng, statistics: &mut Statistics,
+    ) -> Result<Vec<Vec<Literal>>, Error> {
+        let (kappa, pivot) = self.kappa_pivot();
+        let hi = (1.0 + 2f64.sqrt() * (1.0 + kappa) * pivot as f64).ceil() as usize;
+        let lo = (pivot as f64 / (2f64.sqrt() * (1.0 + kappa))).floor() as usize;
+
+        // Few enough models to enumerate them all, then the samples are exactly uniform.
+        let mut cells = Cells::new(new_solver(), cnf, sampling_set, &self.limits);
+        statistics.cells += 1;
+        let models = cells.enumerate(&[], hi + 1);
+        statistics.solver_calls += cells.solver_calls;
+        let models = models?;
+        if models.len() <= hi {
+            if models.is_empty() {
+                return Ok(Vec::new());
+            }
+            return Ok((0..count).map(|_| models.choose(rng).unwrap().clone()).collect());
+        }
+
+        let mut counter = ApproxCounter::new();
+        counter.set_epsilon(0.8);
+        counter.set_delta(0.8);
+        counter.set_sampling_set(sampling_set.to_vec());
+        counter.set_seed(rng.gen());
+        counter.set_limits(self.limits.clone());
+        let estimate = counter.count_with(new_solver, cnf);
+        statistics.solver_calls += counter.last_statistics().solver_calls;
+        let estimate = estimate?.count;
+
+        let q = (estimate.to_f64().log2() + 1.8f64.log2() - (pivot as f64).log2()).ceil().max(0.0) as usize;
+        let last = q.clamp(1, sampling_set.len());
+        let first = q.saturating_sub(3).clamp(1, last);
+
+        let mut samples = Vec::with_capacity(count);
+        let max_attempts = Self::MAX_ATTEMPTS_PER_SAMPLE * count;
+        for _ in 0..max_attempts {
+            if samples.len() == count {
+                break;
+            }
+            let mut cells = Cells::new(new_solver(), cnf, sampling_set, &self.limits);
+            let sample = Self::attempt(&mut cells, first..=last, (lo, hi), rng, statistics);
+            statistics.solver_calls += cells.solver_calls;
+
+            match sample? {
+                Some(sample) => samples.push(sample),
+                None => statistics.failed_attempts += 1,
+            }
+        }
+
+        if samples.len() < count {
+            return Err(Error::ResourceExhausted(format!(
+                "{} of {} samples drawn in {} attempts", samples.len(), count, max_attempts
+            )));
+        }
+        Ok(samples)
+    }
+
+    // Adds random XORs one by one, and draws the sample from the first cell
+    // of the right size.
+    fn attempt<S: IncrementalSolver>(
+        cells: &mut Cells<S>, xors_range: std::ops::RangeInclusive<usize>, (lo, hi): (usize, usize),
+        rng: &mut StdRng, statistics: &mut Statistics,
+    ) -> Result<Option<Vec<Literal>>, Error> {
+        let mut xors: Vec<Xor> = Vec::new();
+        for m in xors_range {
+            while xors.len() < m {
+                xors.push(Xor::random(cells.sampling_set(), rng));
+                statistics.xors += 1;
+            }
+            statistics.cells += 1;
+            let models = cells.enumerate(&xors, hi + 1)?;
+            if lo <= models.len() && models.len() <= hi {
+                return Ok(models.choose(rng).cloned());
+            }
+        }
+        Ok(None)
+    }
+
+    // Every sample walks the sampling set in a random order, picking a random
+    // value for each variable, or the other one if the first makes the formula
+    // unsatisfiable. The last model found witnesses the values picked so far,
+    // so the solver is only called when it disagrees with the pick.
+    fn heuristic<S: IncrementalSolver>(
+        &self, mut solver: S, cnf: &CNF, sampling_set: &[usize], count: usize,
+        rng: &mut StdRng, statistics: &mut Statistics,
+    ) -> Result<Vec<Vec<Literal>>, Error> {
+        solver.add_cnf(cnf);
+        solver.set_limits(self.limits.clone());
+
+        let mut solve = |solver: &mut S, assumptions: &[Literal]| -> Result<bool, Error> {
+            statistics.solver_calls += 1;
+            match solver.solve_with_assumptions(assumptions)? {
+                Outcome::Sat(_) => Ok(true),
+                Outcome::Unsat => Ok(false),
+                Outcome::Unknown(reason) => Err(Error::ResourceExhausted(reason)),
+            }
+        };
+
+        if !solve(&mut solver, &[])? {
+            return Ok(Vec::new());
+        }
+        let witness = |solver: &S| -> Vec<Option<bool>> {
+            sampling_set.iter().map(|v| solver.value(Literal::new(*v, false))).collect()
+        };
+        let mut model = witness(&solver);
+
+        let mut order: Vec<usize> = (0..sampling_set.len()).collect();
+        let mut samples = Vec::with_capacity(count);
+        for _ in 0..count {
+            order.shuffle(rng);
+            let mut assumptions: Vec<Literal> = Vec::with_capacity(order.len());
+
+            for i in order.iter() {
+                let value: bool = rng.gen();
+                let l = Literal::new(sampling_set[*i], !value);
+
+                // Unassigned variables do not occur in the formula, either value goes.
+                if model[*i] != Some(!value) {
+                    assumptions.push(l);
+                    continue;
+                }
+
+                assumptions.push(l);
+                if solve(&mut solver, &assumptions)? {
+                    model = witness(&solver);
+                } else {
+                    assumptions.pop();
+                    assumptions.push(l.neg());
+                }
+            }
+
+            assumptions.sort();
+            samples.push(assumptions);
+        }
+        Ok(samples)
+    }
+}
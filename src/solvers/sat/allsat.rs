@@ -0,0 +1,195 @@
+pub use super::solver::{IncrementalSolver, Error, Outcome};
+use super::cdcl::IncrementalCDCL;
+use super::limits::Limits;
+use crate::p::assignment::Assignment;
+use crate::p::cnf::{CNF, Literal};
+
+// Enumerates the models of a CNF with blocking clauses: after each model the
+// solver gets a clause excluding it, so no model is reported twice.
+#[derive(Clone, Default)]
+pub struct AllSat {
+    projection: Option<Vec<usize>>,
+    max_models: Option<usize>,
+    cubes: bool,
+    limits: Limits,
+}
+
+// Lazy iterator over the models. Each item is a model restricted to the
+// projection variables, or a cube (partial model) when cubes are enabled,
+// the other variables being left unassigned.
+// When the solver gives up, the reason is yielded as an error and the iteration ends.
+pub struct Models<S: IncrementalSolver> {
+    solver: S,
+    variables: Vec<usize>,
+    var_count: usize,
+    cubes: bool,
+    remaining: Option<usize>,
+    done: bool,
+
+    clauses: Vec<Vec<Literal>>,
+    occurrences: Vec<Vec<usize>>,
+}
+
+impl AllSat {
+    pub fn new() -> AllSat {
+        AllSat::default()
+    }
+
+    // Only these variables are reported and blocked, so every assignment
+    // to them that extends to a model is reported once.
+    pub fn set_projection(&mut self, value: Vec<usize>) {
+        self.projection = Some(value);
+    }
+
+    pub fn set_max_models(&mut self, value: usize) {
+        self.max_models = Some(value);
+    }
+
+    // Report cubes: partial assignments whose every extension is a model.
+    // A cube is blocked as a whole, and cubes never overlap.
+    pub fn set_cubes(&mut self, value: bool) {
+        self.cubes = value;
+    }
+
+    // Applied to every solver call, a deadline or cancellation covers the whole enumeration.
+    pub fn set_limits(&mut self, value: Limits) {
+        self.limits = value;
+    }
+
+    pub fn models(&self, cnf: &CNF) -> Models<IncrementalCDCL> {
+        self.models_with(IncrementalCDCL::new(), cnf)
+    }
+
+    pub fn models_with<S: IncrementalSolver>(&self, mut solver: S, cnf: &CNF) -> Models<S> {
+        solver.add_cnf(cnf);
+        solver.set_limits(self.limits.clone());
+
+        let variables: Vec<usize> = match &self.projection {
+            Some(projection) => {
+                let mut variables = projection.clone();
+                variables.sort();
+                variables.dedup();
+                variables
+            },
+            None => (1..=cnf.var_count()).collect(),
+        };
+
+        let n = variables.iter().cloned().max().unwrap_or(0).max(cnf.var_count()) + 1;
+        let mut models = Models {
+            solver,
+            variables,
+            var_count: n - 1,
+            cubes: self.cubes,
+            remaining: self.max_models,
+            done: false,
+            clauses: Vec::new(),
+            occurrences: vec![Vec::new(); 2 * n],
+        };
+
+        if self.cubes {
+            for clause in cnf.clauses().iter() {
+                if !clause.iter().any(|l| clause.contains(&l.neg())) {
+                    models.add_clause(clause.to_vec());
+                }
+            }
+        }
+        models
+    }
+}
+
+impl<S: IncrementalSolver> Models<S> {
+    pub fn into_solver(self) -> S {
+        self.solver
+    }
+
+
+    fn add_clause(&mut self, clause: Vec<Literal>) {
+        for l in clause.iter() {
+            self.occurrences[l.index()].push(self.clauses.len());
+        }
+        self.clauses.push(clause);
+    }
+
+    // Unassigned variables are free in the model, they are taken as false.
+    fn full_model(&self) -> Vec<Literal> {
+        self.variables.iter()
+            .map(|v| Literal::new(*v, self.solver.value(Literal::new(*v, false)) != Some(true)))
+            .collect()
+    }
+
+    // Drops projected literals from the model while every clause, blocking
+    // clauses included, stays satisfied by the literals left. The literals of
+    // the other variables stay fixed, so they witness every extension of the cube.
+    fn shrink_model(&self) -> Vec<Literal> {
+        let mut true_count: Vec<usize> = self.clauses.iter()
+            .map(|c| c.iter().filter(|l| self.solver.value(**l) == Some(true)).count())
+            .collect();
+
+        let mut cube = Vec::new();
+        for v in self.variables.iter() {
+            let l = match self.solver.value(Literal::new(*v, false)) {
+                Some(value) => Literal::new(*v, !value),
+                None => continue,
+            };
+
+            let occurrences = &self.occurrences[l.index()];
+            if occurrences.iter().all(|c| true_count[*c] >= 2) {
+                for c in occurrences.iter() {
+                    true_count[*c] -= 1;
+                }
+            } else {
+                cube.push(l);
+            }
+        }
+        cube
+    }
+}
+
+impl<S: IncrementalSolver> Iterator for Models<S> {
+    type Item = Result<Assignment, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done || self.remaining == Some(0) {
+            return None;
+        }
+
+        let outcome = match self.solver.solve_with_assumptions(&[]) {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                self.done = true;
+                return Some(Err(e));
+            },
+        };
+
+        match outcome {
+            Outcome::Sat(_) => {},
+            Outcome::Unsat => {
+                self.done = true;
+                return None;
+            },
+            Outcome::Unknown(reason) => {
+                self.done = true;
+                return Some(Err(Error::ResourceExhausted(reason)));
+            },
+        }
+
+        let model = if self.cubes { self.shrink_model() } else { self.full_model() };
+        let blocking: Vec<Literal> = model.iter().map(|l| l.neg()).collect();
+
+        if blocking.is_empty() {
+            self.done = true;
+        } else {
+            self.solver.add_clause(blocking.clone());
+            if self.cubes {
+                self.add_clause(blocking);
+            }
+        }
+
+        let mut values = vec![None; self.var_count + 1];
+        for l in model.iter() {
+            values[l.get_var()] = Some(!l.is_negative());
+        }
+        self.remaining = self.remaining.map(|r| r - 1);
+        Some(Ok(Assignment::from_values(values)))
+    }
+}
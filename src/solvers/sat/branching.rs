@@ -0,0 +1,210 @@
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use crate::p::cnf::Literal;
+
+// Chooses the literal DPLL decides next, reading the search state through
+// the view. The returned literal is set true first, unless Phase overrides it.
+pub trait BranchingHeuristic: Send + Sync {
+    fn pick(&self, view: &dyn BranchingView, rng: &mut StdRng) -> Option<Literal>;
+}
+
+// The state of the search at a decision, borrowed, so nothing is copied per
+// decision. Clauses are numbered 0..clause_count() and keep their assigned
+// literals; every unsatisfied one has an unassigned literal left.
+pub trait BranchingView {
+    // Variables are 1..=var_count().
+    fn var_count(&self) -> usize;
+    fn clause_count(&self) -> usize;
+    fn clause(&self, c: usize) -> &[Literal];
+    fn is_satisfied(&self, c: usize) -> bool;
+
+    // Unassigned literals of an unsatisfied clause.
+    fn unassigned_len(&self, c: usize) -> usize;
+
+    fn value(&self, l: Literal) -> Option<bool>;
+
+    // Unsatisfied clauses containing the literal.
+    fn occurrences(&self, l: Literal) -> usize;
+}
+
+// Which value of the picked variable is tried first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Phase {
+    #[default]
+    Heuristic,  // the literal as picked
+    Positive,
+    Negative,
+    Random,
+}
+
+impl Phase {
+    pub fn apply(&self, l: Literal, rng: &mut StdRng) -> Literal {
+        match self {
+            Phase::Heuristic => l,
+            Phase::Positive => Literal::new(l.get_var(), false),
+            Phase::Negative => Literal::new(l.get_var(), true),
+            Phase::Random => Literal::new(l.get_var(), rng.gen()),
+        }
+    }
+}
+
+// Dynamic Largest Individual Sum: the literal occurring in most clauses.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DLIS;
+
+// Maximum Occurrences in clauses of Minimum Size, scored with Freeman's
+// formula (f(x) + f(-x)) * 2^k + f(x) * f(-x) over the shortest clauses.
+#[derive(Clone, Copy, Debug)]
+pub struct MOMS {
+    pub k: u32,
+}
+
+// Every clause adds 2^-|c| to the score of its literals. The one-sided
+// variant picks the best literal, the two-sided one the variable with the best
+// J(x) + J(-x), trying the literal with the larger score first.
+#[derive(Clone, Copy, Debug)]
+pub struct JeroslowWang {
+    pub two_sided: bool,
+}
+
+// The literal occurring in most of the shortest clauses.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ShortestClauseOccurrences;
+
+// A uniformly random variable among the ones left, with a random phase.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RandomLiteral;
+
+impl Default for MOMS {
+    fn default() -> Self {
+        MOMS { k: 10 }
+    }
+}
+
+impl JeroslowWang {
+    pub fn one_sided() -> JeroslowWang {
+        JeroslowWang { two_sided: false }
+    }
+
+    pub fn two_sided() -> JeroslowWang {
+        JeroslowWang { two_sided: true }
+    }
+}
+
+impl Default for JeroslowWang {
+    fn default() -> Self {
+        Self::two_sided()
+    }
+}
+
+impl BranchingHeuristic for DLIS {
+    fn pick(&self, view: &dyn BranchingView, _rng: &mut StdRng) -> Option<Literal> {
+        let mut best: Option<(Literal, usize)> = None;
+        for v in 1..=view.var_count() {
+            for l in [Literal::new(v, false), Literal::new(v, true)] {
+                let count = view.occurrences(l);
+                if count > 0 && view.value(l).is_none() && best.is_none_or(|(_, b)| count > b) {
+                    best = Some((l, count));
+                }
+            }
+        }
+        best.map(|(l, _)| l)
+    }
+}
+
+impl BranchingHeuristic for MOMS {
+    fn pick(&self, view: &dyn BranchingView, _rng: &mut StdRng) -> Option<Literal> {
+        let min = shortest(view);
+        let factor = 2f64.powi(self.k as i32);
+        best_variable(&scores(view, |len| if len == min { 1.0 } else { 0.0 }), |p, n| (p + n) * factor + p * n)
+    }
+}
+
+impl BranchingHeuristic for JeroslowWang {
+    fn pick(&self, view: &dyn BranchingView, _rng: &mut StdRng) -> Option<Literal> {
+        let scores = scores(view, |len| 2f64.powi(-(len as i32)));
+        if self.two_sided {
+            best_variable(&scores, |p, n| p + n)
+        } else {
+            best_literal(&scores)
+        }
+    }
+}
+
+impl BranchingHeuristic for ShortestClauseOccurrences {
+    fn pick(&self, view: &dyn BranchingView, _rng: &mut StdRng) -> Option<Literal> {
+        let min = shortest(view);
+        best_literal(&scores(view, |len| if len == min { 1.0 } else { 0.0 }))
+    }
+}
+
+impl BranchingHeuristic for RandomLiteral {
+    fn pick(&self, view: &dyn BranchingView, rng: &mut StdRng) -> Option<Literal> {
+        let variables: Vec<usize> = (1..=view.var_count())
+            .filter(|v| {
+                let l = Literal::new(*v, false);
+                view.value(l).is_none() && view.occurrences(l) + view.occurrences(l.neg()) > 0
+            })
+            .collect();
+
+        if variables.is_empty() {
+            return None;
+        }
+
+        let var = variables[rng.gen_range(0..variables.len())];
+        Some(Literal::new(var, rng.gen()))
+    }
+}
+
+// Unassigned length of the shortest unsatisfied clause.
+fn shortest(view: &dyn BranchingView) -> usize {
+    (0..view.clause_count())
+        .filter(|c| !view.is_satisfied(*c))
+        .map(|c| view.unassigned_len(c))
+        .min()
+        .unwrap_or(0)
+}
+
+// Sum of weight(|c|) over the unsatisfied clauses c containing each
+// unassigned literal, |c| counting unassigned literals. Indexed by l.index().
+fn scores(view: &dyn BranchingView, weight: impl Fn(usize) -> f64) -> Vec<f64> {
+    let mut scores = vec![0.0; 2 * view.var_count() + 2];
+
+    for c in (0..view.clause_count()).filter(|c| !view.is_satisfied(*c)) {
+        let w = weight(view.unassigned_len(c));
+        if w == 0.0 {
+            continue;
+        }
+        for l in view.clause(c).iter().filter(|l| view.value(**l).is_none()) {
+            scores[l.index()] += w;
+        }
+    }
+    scores
+}
+// Ties go to the smallest literal, so the choice does not depend on clause order.
+fn best_literal(scores: &[f64]) -> Option<Literal> {
+    let mut best: Option<usize> = None;
+    for i in 0..scores.len() {
+        if scores[i] > 0.0 && best.is_none_or(|b| scores[i] > scores[b]) {
+            best = Some(i);
+        }
+    }
+    best.map(Literal::from_index)
+}
+
+fn best_variable(scores: &[f64], combine: impl Fn(f64, f64) -> f64) -> Option<Literal> {
+    let mut best: Option<(usize, f64)> = None;
+    for v in 0..scores.len() / 2 {
+        let (p, n) = (scores[2 * v], scores[2 * v + 1]);
+        if p + n == 0.0 {
+            continue;
+        }
+
+        let score = combine(p, n);
+        if best.is_none_or(|(_, b)| score > b) {
+            best = Some((v, score));
+        }
+    }
+    best.map(|(v, _)| Literal::new(v, scores[2 * v + 1] > scores[2 * v]))
+}
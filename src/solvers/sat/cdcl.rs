@@ -0,0 +1,842 @@
+use std::io;
+
+pub use super::solver::{Solver, ProofSolver, IncrementalSolver, Outcome, Error};
+use super::limits::{Limits, Usage};
+use crate::p::assignment::Assignment;
+use crate::p::cnf::{CNF, Literal};
+use crate::proof::drat::ProofTracer;
+
+#[derive(Clone)]
+pub struct CDCL {
+    restart_base: usize,
+    var_decay: f64,
+    clause_decay: f64,
+    learnt_size_factor: f64,
+    learnt_size_inc: f64,
+}
+
+struct Clause {
+    literals: Vec<Literal>,
+    learnt: bool,
+    deleted: bool,
+    activity: f64,
+    lbd: usize,
+}
+
+// Binary max-heap of variables ordered by activity, with positions
+// so that a variable can be bumped or looked up in O(log n).
+struct VarOrder {
+    heap: Vec<usize>,
+    position: Vec<Option<usize>>,
+}
+
+impl VarOrder {
+    fn new(n: usize) -> VarOrder {
+        VarOrder {
+            heap: Vec::new(),
+            position: vec![None; n],
+        }
+    }
+
+    fn contains(&self, v: usize) -> bool {
+        self.position[v].is_some()
+    }
+
+    fn insert(&mut self, v: usize, activity: &[f64]) {
+        if self.contains(v) {
+            return;
+        }
+        self.position[v] = Some(self.heap.len());
+        self.heap.push(v);
+        self.sift_up(self.heap.len() - 1, activity);
+    }
+
+    fn pop(&mut self, activity: &[f64]) -> Option<usize> {
+        if self.heap.is_empty() {
+            return None;
+        }
+        let top = self.heap.swap_remove(0);
+        self.position[top] = None;
+        if !self.heap.is_empty() {
+            self.position[self.heap[0]] = Some(0);
+            self.sift_down(0, activity);
+        }
+        Some(top)
+    }
+
+    fn bumped(&mut self, v: usize, activity: &[f64]) {
+        if let Some(i) = self.position[v] {
+            self.sift_up(i, activity);
+        }
+    }
+
+    fn sift_up(&mut self, mut i: usize, activity: &[f64]) {
+        let v = self.heap[i];
+        while i > 0 {
+            let parent = (i - 1) / 2;
+            if activity[self.heap[parent]] >= activity[v] {
+                break;
+            }
+            self.heap[i] = self.heap[parent];
+            self.position[self.heap[i]] = Some(i);
+            i = parent;
+        }
+        self.heap[i] = v;
+        self.position[v] = Some(i);
+    }
+
+    fn sift_down(&mut self, mut i: usize, activity: &[f64]) {
+        let v = self.heap[i];
+        loop {
+            let left = 2 * i + 1;
+            if left >= self.heap.len() {
+                break;
+            }
+            let right = left + 1;
+            let child = if right < self.heap.len() && activity[self.heap[right]] > activity[self.heap[left]] {
+                right
+            } else {
+                left
+            };
+            if activity[self.heap[child]] <= activity[v] {
+                break;
+            }
+            self.heap[i] = self.heap[child];
+            self.position[self.heap[i]] = Some(i);
+            i = child;
+        }
+        self.heap[i] = v;
+        self.position[v] = Some(i);
+    }
+}
+
+#[derive(Clone, PartialEq, Eq)]
+enum Status {
+    Sat,
+    Unsat,
+    Interrupted(String),
+}
+
+struct Search<'a> {
+    params: CDCL,
+    ok: bool,
+
+    clauses: Vec<Clause>,
+    watches: Vec<Vec<usize>>,
+    originals: usize,
+    learnts: usize,
+    max_learnts: f64,
+
+    assigns: Vec<Option<bool>>,
+    level: Vec<usize>,
+    reason: Vec<Option<usize>>,
+    polarity: Vec<bool>,
+    activity: Vec<f64>,
+    order: VarOrder,
+    seen: Vec<bool>,
+
+    trail: Vec<Literal>,
+    trail_lim: Vec<usize>,
+    qhead: usize,
+
+    var_inc: f64,
+    cla_inc: f64,
+
+    failed: Vec<Literal>,
+
+    limits: Limits,
+    usage: Usage,
+
+    proof: Option<&'a mut dyn ProofTracer>,
+    proof_error: Option<io::Error>,
+}
+
+// Keeps the clause database, learnt clauses and activities between calls.
+pub struct IncrementalCDCL {
+    search: Search<'static>,
+    model: Assignment,
+}
+
+impl Solver for CDCL {
+    fn solve_with_limits(&self, cnf: CNF, limits: &Limits) -> Result<Outcome, Error> {
+        self.solve_cdcl(&cnf, None, limits)
+    }
+}
+
+impl ProofSolver for CDCL {
+    fn solve_with_proof(&self, cnf: CNF, proof: &mut dyn ProofTracer) -> Result<Outcome, Error> {
+        self.solve_cdcl(&cnf, Some(proof), &Limits::new())
+    }
+}
+
+impl IncrementalSolver for IncrementalCDCL {
+    fn add_clause(&mut self, clause: Vec<Literal>) {
+        self.search.add_clause(&clause);
+    }
+
+    fn solve_with_assumptions(&mut self, assumptions: &[Literal]) -> Result<Outcome, Error> {
+        self.model = Assignment::default();
+        self.search.params.check_parameters()?;
+
+        let outcome = match self.search.solve(assumptions) {
+            Status::Sat => {
+                self.model = self.search.model();
+                Outcome::Sat(self.model.clone())
+            },
+            Status::Unsat => Outcome::Unsat,
+            Status::Interrupted(reason) => Outcome::Unknown(reason),
+        };
+
+        self.search.cancel_until(0);
+        Ok(outcome)
+    }
+
+    fn set_limits(&mut self, limits: Limits) {
+        self.search.limits = limits;
+    }
+
+    fn value(&self, l: Literal) -> Option<bool> {
+        self.model.value(l)
+    }
+
+    fn failed_assumptions(&self) -> Vec<Literal> {
+        self.search.failed.clone()
+    }
+}
+
+impl IncrementalCDCL {
+    pub fn new() -> IncrementalCDCL {
+        CDCL::new().incremental()
+    }
+}
+
+impl Default for IncrementalCDCL {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Default for CDCL {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CDCL {
+    pub fn new() -> CDCL {
+        CDCL {
+            restart_base: 100,
+            var_decay: 0.95,
+            clause_decay: 0.999,
+            learnt_size_factor: 1.0 / 3.0,
+            learnt_size_inc: 1.1,
+        }
+    }
+
+    pub fn set_restart_base(&mut self, value: usize) {
+        self.restart_base = value;
+    }
+
+    pub fn set_var_decay(&mut self, value: f64) {
+        self.var_decay = value;
+    }
+
+    pub fn set_clause_decay(&mut self, value: f64) {
+        self.clause_decay = value;
+    }
+
+    pub fn set_learnt_size_factor(&mut self, value: f64) {
+        self.learnt_size_factor = value;
+    }
+
+    pub fn set_learnt_size_inc(&mut self, value: f64) {
+        self.learnt_size_inc = value;
+    }
+
+    // A restart base of 0 would restart before every decision, and activities
+    // are divided by the decays, so those are rejected rather than looping.
+    fn check_parameters(&self) -> Result<(), Error> {
+        if self.restart_base == 0 {
+            return Err(Error::InvalidInput("restart base must be positive".to_string()));
+        }
+        for (name, decay) in [("variable", self.var_decay), ("clause", self.clause_decay)] {
+            if !(decay > 0.0 && decay <= 1.0) {
+                return Err(Error::InvalidInput(format!("{} decay must be in (0, 1], got {}", name, decay)));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn incremental(&self) -> IncrementalCDCL {
+        IncrementalCDCL {
+            search: Search::new(self.clone(), None),
+            model: Assignment::default(),
+        }
+    }
+
+    pub fn solve_cdcl(&self, cnf: &CNF, proof: Option<&mut dyn ProofTracer>, limits: &Limits) -> Result<Outcome, Error> {
+        self.check_parameters()?;
+        let mut search = Search::new(self.clone(), proof);
+        search.limits = limits.clone();
+
+        search.reserve(cnf.var_count());
+        for v in 1..=cnf.var_count() {
+            search.order.insert(v, &search.activity);
+        }
+
+        for clause in cnf.clauses().iter() {
+            if !search.add_clause(clause) {
+                break;
+            }
+        }
+
+        let outcome = match search.solve(&[]) {
+            Status::Sat => Outcome::Sat(search.model()),
+            Status::Unsat => {
+                search.trace(false, &[]);
+                Outcome::Unsat
+            },
+            Status::Interrupted(reason) => Outcome::Unknown(reason),
+        };
+
+        match search.proof_error {
+            Some(e) => Err(Error::from(e)),
+            None => Ok(outcome),
+        }
+    }
+
+    // Finite Luby sequence: 1 1 2 1 1 2 4 1 1 2 1 1 2 4 8 ...
+    fn luby(mut i: usize) -> usize {
+        let mut size = 1;
+        let mut seq = 0;
+        while size < i + 1 {
+            seq += 1;
+            size = 2 * size + 1;
+        }
+        while size - 1 != i {
+            size = (size - 1) / 2;
+            seq -= 1;
+            i %= size;
+        }
+        1 << seq
+    }
+}
+
+impl<'a> Search<'a> {
+    fn new(params: CDCL, proof: Option<&'a mut dyn ProofTracer>) -> Search<'a> {
+        Search {
+            params,
+            ok: true,
+            clauses: Vec::new(),
+            watches: Vec::new(),
+            originals: 0,
+            learnts: 0,
+            max_learnts: 0.0,
+            assigns: Vec::new(),
+            level: Vec::new(),
+            reason: Vec::new(),
+            polarity: Vec::new(),
+            activity: Vec::new(),
+            order: VarOrder::new(0),
+            seen: Vec::new(),
+            trail: Vec::new(),
+            trail_lim: Vec::new(),
+            qhead: 0,
+            var_inc: 1.0,
+            cla_inc: 1.0,
+            failed: Vec::new(),
+            limits: Limits::new(),
+            usage: Usage::default(),
+            proof,
+            proof_error: None,
+        }
+    }
+
+    // Makes room for variables up to max_var (inclusive).
+    fn reserve(&mut self, max_var: usize) {
+        let n = max_var + 1;
+        if n <= self.assigns.len() {
+            return;
+        }
+        self.watches.resize(2 * n, Vec::new());
+        self.assigns.resize(n, None);
+        self.level.resize(n, 0);
+        self.reason.resize(n, None);
+        self.polarity.resize(n, true);
+        self.activity.resize(n, 0.0);
+        self.order.position.resize(n, None);
+        self.seen.resize(n, false);
+    }
+
+    // Reports a lemma (or its deletion) to the proof, remembering the first write error.
+    fn trace(&mut self, delete: bool, clause: &[Literal]) {
+        if self.proof_error.is_some() {
+            return;
+        }
+        if let Some(proof) = self.proof.as_mut() {
+            let result = if delete { proof.delete(clause) } else { proof.add(clause) };
+            self.proof_error = result.err();
+        }
+    }
+
+
+    fn value(&self, l: Literal) -> Option<bool> {
+        self.assigns[l.get_var()].map(|v| v != l.is_negative())
+    }
+
+    fn decision_level(&self) -> usize {
+        self.trail_lim.len()
+    }
+
+    // Adds a clause at level 0, simplified by the top-level assignment.
+    // Returns false once the formula is known to be unsatisfiable.
+    fn add_clause(&mut self, clause: &[Literal]) -> bool {
+        if !self.ok {
+            return false;
+        }
+        self.cancel_until(0);
+
+        let mut clause = clause.to_vec();
+        clause.sort();
+        clause.dedup();
+
+        // Variables of a tautology are still given a value in models.
+        for l in clause.iter() {
+            self.reserve(l.get_var());
+            self.order.insert(l.get_var(), &self.activity);
+        }
+
+        for l in clause.iter() {
+            if clause.contains(&l.neg()) {
+                return true;
+            }
+        }
+
+        if clause.iter().any(|l| self.value(*l) == Some(true)) {
+            return true;
+        }
+        clause.retain(|l| self.value(*l).is_none());
+
+        match clause.len() {
+            0 => self.ok = false,
+            1 => {
+                self.enqueue(clause[0], None);
+                self.ok = self.propagate().is_none();
+            },
+            _ => {
+                self.attach(clause, false);
+                self.originals += 1;
+            }
+        }
+
+        self.ok
+    }
+
+    fn attach(&mut self, literals: Vec<Literal>, learnt: bool) -> usize {
+        let cref = self.clauses.len();
+        self.watches[literals[0].index()].push(cref);
+        self.watches[literals[1].index()].push(cref);
+        self.clauses.push(Clause {
+            literals,
+            learnt,
+            deleted: false,
+            activity: 0.0,
+            lbd: 0,
+        });
+        if learnt {
+            self.learnts += 1;
+        }
+        cref
+    }
+
+    fn enqueue(&mut self, l: Literal, reason: Option<usize>) {
+        let v = l.get_var();
+        self.assigns[v] = Some(!l.is_negative());
+        self.level[v] = self.decision_level();
+        self.reason[v] = reason;
+        self.trail.push(l);
+    }
+
+    // Two-watched-literal unit propagation. Returns a conflicting clause, if any.
+    fn propagate(&mut self) -> Option<usize> {
+        while self.qhead < self.trail.len() {
+            let p = self.trail[self.qhead];
+            self.qhead += 1;
+
+            let false_lit = p.neg();
+            let mut ws = std::mem::take(&mut self.watches[false_lit.index()]);
+            let mut kept = 0;
+            let mut conflict = None;
+
+            let mut i = 0;
+            while i < ws.len() {
+                let cref = ws[i];
+                i += 1;
+
+                if self.clauses[cref].deleted {
+                    continue;
+                }
+
+                let literals = &mut self.clauses[cref].literals;
+                if literals[0] == false_lit {
+                    literals.swap(0, 1);
+                }
+
+                let first = literals[0];
+                if self.assigns[first.get_var()].map(|v| v != first.is_negative()) == Some(true) {
+                    ws[kept] = cref;
+                    kept += 1;
+                    continue;
+                }
+
+                let mut moved = false;
+                for k in 2..literals.len() {
+                    let l = literals[k];
+                    if self.assigns[l.get_var()].map(|v| v != l.is_negative()) != Some(false) {
+                        literals.swap(1, k);
+                        self.watches[l.index()].push(cref);
+                        moved = true;
+                        break;
+                    }
+                }
+                if moved {
+                    continue;
+                }
+
+                ws[kept] = cref;
+                kept += 1;
+
+                if self.value(first) == Some(false) {
+                    conflict = Some(cref);
+                    while i < ws.len() {
+                        ws[kept] = ws[i];
+                        kept += 1;
+                        i += 1;
+                    }
+                } else {
+                    self.enqueue(first, Some(cref));
+                }
+            }
+
+            ws.truncate(kept);
+            self.watches[false_lit.index()] = ws;
+
+            if conflict.is_some() {
+                self.qhead = self.trail.len();
+                return conflict;
+            }
+        }
+
+        None
+    }
+
+    // First-UIP conflict analysis. Returns the learnt clause (asserting
+    // literal first, literal of the backjump level second) and the backjump level.
+    fn analyze(&mut self, mut confl: usize) -> (Vec<Literal>, usize) {
+        let mut learnt = Vec::new();
+        let mut path = 0;
+        let mut p: Option<Literal> = None;
+        let mut index = self.trail.len();
+
+        loop {
+            if self.clauses[confl].learnt {
+                self.bump_clause(confl);
+            }
+
+            let start = if p.is_none() { 0 } else { 1 };
+            for k in start..self.clauses[confl].literals.len() {
+                let q = self.clauses[confl].literals[k];
+                let v = q.get_var();
+                if self.seen[v] || self.level[v] == 0 {
+                    continue;
+                }
+                self.bump_var(v);
+                self.seen[v] = true;
+                if self.level[v] >= self.decision_level() {
+                    path += 1;
+                } else {
+                    learnt.push(q);
+                }
+            }
+
+            loop {
+                index -= 1;
+                if self.seen[self.trail[index].get_var()] {
+                    break;
+                }
+            }
+            let l = self.trail[index];
+            self.seen[l.get_var()] = false;
+            p = Some(l);
+            path -= 1;
+            if path == 0 {
+                break;
+            }
+            confl = self.reason[l.get_var()].unwrap();
+        }
+        learnt.insert(0, p.unwrap().neg());
+
+        // Drop literals implied by the rest of the clause through their reasons.
+        let mut minimized = vec![learnt[0]];
+        for &l in learnt.iter().skip(1) {
+            let redundant = match self.reason[l.get_var()] {
+                None => false,
+                Some(r) => self.clauses[r].literals.iter().skip(1).all(|q| {
+                    self.seen[q.get_var()] || self.level[q.get_var()] == 0
+                }),
+            };
+            if !redundant {
+                minimized.push(l);
+            }
+        }
+        for l in learnt.iter() {
+            self.seen[l.get_var()] = false;
+        }
+        let mut learnt = minimized;
+
+        let mut backjump = 0;
+        if learnt.len() > 1 {
+            let mut max_i = 1;
+            for k in 2..learnt.len() {
+                if self.level[learnt[k].get_var()] > self.level[learnt[max_i].get_var()] {
+                    max_i = k;
+                }
+            }
+            learnt.swap(1, max_i);
+            backjump = self.level[learnt[1].get_var()];
+        }
+
+        (learnt, backjump)
+    }
+
+    fn cancel_until(&mut self, level: usize) {
+        if self.decision_level() <= level {
+            return;
+        }
+        let lim = self.trail_lim[level];
+        for k in (lim..self.trail.len()).rev() {
+            let v = self.trail[k].get_var();
+            self.assigns[v] = None;
+            self.reason[v] = None;
+            self.polarity[v] = self.trail[k].is_negative();
+            self.order.insert(v, &self.activity);
+        }
+        self.trail.truncate(lim);
+        self.trail_lim.truncate(level);
+        self.qhead = lim;
+    }
+
+    fn bump_var(&mut self, v: usize) {
+        self.activity[v] += self.var_inc;
+        if self.activity[v] > 1e100 {
+            for a in self.activity.iter_mut() {
+                *a *= 1e-100;
+            }
+            self.var_inc *= 1e-100;
+        }
+        self.order.bumped(v, &self.activity);
+    }
+
+    fn bump_clause(&mut self, cref: usize) {
+        self.clauses[cref].activity += self.cla_inc;
+        if self.clauses[cref].activity > 1e20 {
+            for c in self.clauses.iter_mut().filter(|c| c.learnt) {
+                c.activity *= 1e-20;
+            }
+            self.cla_inc *= 1e-20;
+        }
+    }
+
+    fn decay(&mut self) {
+        self.var_inc /= self.params.var_decay;
+        self.cla_inc /= self.params.clause_decay;
+    }
+
+    fn lbd(&self, literals: &[Literal]) -> usize {
+        let mut levels: Vec<usize> = literals.iter().map(|l| self.level[l.get_var()]).collect();
+        levels.sort_unstable();
+        levels.dedup();
+        levels.len()
+    }
+
+    fn is_locked(&self, cref: usize) -> bool {
+        let first = self.clauses[cref].literals[0];
+        self.reason[first.get_var()] == Some(cref) && self.value(first) == Some(true)
+    }
+
+    // Removes the less useful half of learnt clauses, keeping glue clauses and reasons.
+    fn reduce_db(&mut self) {
+        let mut candidates: Vec<usize> = (0..self.clauses.len())
+            .filter(|&c| {
+                let clause = &self.clauses[c];
+                clause.learnt && !clause.deleted && clause.lbd > 2 && !self.is_locked(c)
+            })
+            .collect();
+
+        candidates.sort_by(|&a, &b| {
+            let (a, b) = (&self.clauses[a], &self.clauses[b]);
+            b.lbd.cmp(&a.lbd).then(a.activity.partial_cmp(&b.activity).unwrap())
+        });
+
+        for &cref in candidates.iter().take(candidates.len() / 2) {
+            let literals = std::mem::take(&mut self.clauses[cref].literals);
+            self.trace(true, &literals);
+            self.usage.derived_literals -= literals.len();
+            self.clauses[cref].deleted = true;
+            self.learnts -= 1;
+        }
+    }
+
+    fn pick_branch_literal(&mut self) -> Option<Literal> {
+        while let Some(v) = self.order.pop(&self.activity) {
+            if self.assigns[v].is_none() {
+                return Some(Literal::new(v, self.polarity[v]));
+            }
+        }
+        None
+    }
+
+    // Collects the assumptions responsible for the assumption p being false.
+    fn analyze_final(&mut self, p: Literal) {
+        self.failed.clear();
+        self.failed.push(p);
+        if self.decision_level() == 0 {
+            return;
+        }
+
+        self.seen[p.get_var()] = true;
+        for k in (self.trail_lim[0]..self.trail.len()).rev() {
+            let l = self.trail[k];
+            let v = l.get_var();
+            if !self.seen[v] {
+                continue;
+            }
+            match self.reason[v] {
+                None => self.failed.push(l),
+                Some(r) => {
+                    for q in self.clauses[r].literals.iter().skip(1) {
+                        if self.level[q.get_var()] > 0 {
+                            self.seen[q.get_var()] = true;
+                        }
+                    }
+                },
+            }
+            self.seen[v] = false;
+        }
+        self.seen[p.get_var()] = false;
+    }
+
+    // Assumptions are decided first, one per decision level. Leaves the
+    // final assignment on the trail; the caller backtracks when done.
+    fn solve(&mut self, assumptions: &[Literal]) -> Status {
+        self.failed.clear();
+        let derived_literals = self.usage.derived_literals;
+        self.usage = Usage::default();
+        self.usage.derived_literals = derived_literals;
+        if !self.ok {
+            return Status::Unsat;
+        }
+        for l in assumptions.iter() {
+            self.reserve(l.get_var());
+            self.order.insert(l.get_var(), &self.activity);
+        }
+        self.max_learnts = (self.originals as f64 * self.params.learnt_size_factor).max(100.0);
+
+        if self.propagate().is_some() {
+            self.ok = false;
+            return Status::Unsat;
+        }
+
+        let mut restarts = 0;
+        let mut conflicts_until_restart = CDCL::luby(restarts) * self.params.restart_base;
+
+        loop {
+            match self.propagate() {
+                Some(confl) => {
+                    if self.decision_level() == 0 {
+                        self.ok = false;
+                        return Status::Unsat;
+                    }
+
+                    let (learnt, backjump) = self.analyze(confl);
+                    self.trace(false, &learnt);
+                    self.usage.conflicts += 1;
+                    self.usage.derived_literals += learnt.len();
+                    self.cancel_until(backjump);
+
+                    if learnt.len() == 1 {
+                        self.enqueue(learnt[0], None);
+                    } else {
+                        let lbd = self.lbd(&learnt);
+                        let asserting = learnt[0];
+                        let cref = self.attach(learnt, true);
+                        self.clauses[cref].lbd = lbd;
+                        self.bump_clause(cref);
+                        self.enqueue(asserting, Some(cref));
+                    }
+
+                    self.decay();
+                    conflicts_until_restart = conflicts_until_restart.saturating_sub(1);
+
+                    if let Some(reason) = self.limits.check(&mut self.usage) {
+                        return Status::Interrupted(reason);
+                    }
+                },
+                None => {
+                    if conflicts_until_restart == 0 {
+                        restarts += 1;
+                        conflicts_until_restart = CDCL::luby(restarts) * self.params.restart_base;
+                        self.cancel_until(0);
+                        continue;
+                    }
+
+                    if self.learnts as f64 - self.trail.len() as f64 >= self.max_learnts {
+                        self.reduce_db();
+                        self.max_learnts *= self.params.learnt_size_inc;
+                    }
+
+                    let mut next = None;
+                    while self.decision_level() < assumptions.len() {
+                        let p = assumptions[self.decision_level()];
+                        match self.value(p) {
+                            Some(true) => self.trail_lim.push(self.trail.len()),
+                            Some(false) => {
+                                self.analyze_final(p);
+                                return Status::Unsat;
+                            },
+                            None => {
+                                next = Some(p);
+                                break;
+                            }
+                        }
+                    }
+
+                    if next.is_none() {
+                        next = self.pick_branch_literal();
+                    }
+
+                    match next {
+                        None => return Status::Sat,
+                        Some(l) => {
+                            self.usage.decisions += 1;
+                            if let Some(reason) = self.limits.check(&mut self.usage) {
+                                return Status::Interrupted(reason);
+                            }
+                            self.trail_lim.push(self.trail.len());
+                            self.enqueue(l, None);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Variables below the largest one that occur in no clause are never
+    // decided, they are taken as false.
+    fn model(&self) -> Assignment {
+        let values = self.assigns.iter()
+            .map(|value| value.or(Some(false)))
+            .collect();
+        Assignment::from_values(values)
+    }
+}
@@ -1,313 +1,481 @@
-use std::collections::{HashSet};
+use std::cell::Cell;
+use std::io;
 
-pub use super::solver::{Solver, Error};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+pub use super::solver::{Solver, ProofSolver, Outcome, Error};
+pub use super::branching::{BranchingHeuristic, BranchingView, Phase};
+use super::branching::JeroslowWang;
+use super::limits::{Limits, Usage};
+use crate::p::assignment::Assignment;
 use crate::p::cnf::{CNF, Literal};
+use crate::proof::drat::ProofTracer;
 
-pub struct DPLL {}
+pub struct DPLL {
+    heuristic: Box<dyn BranchingHeuristic>,
+    phase: Phase,
+    seed: Option<u64>,
 
-impl Solver for DPLL {
-    fn solve(&self, cnf: CNF) -> Result<Vec<Literal>, Error> {
-        Self::solve_dpll(cnf.clone())
-    }
+    statistics: Cell<Statistics>,
 }
 
-impl DPLL {
-    pub fn new() -> DPLL {
-        DPLL {}
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Statistics {
+    pub decisions: u64,
+    pub propagations: u64,  // literals assigned by unit propagation
+    pub pure_literals: u64,
+    pub max_depth: u64,  // deepest stack of decisions
+    pub backtracks: u64,  // refuted decisions
+}
+
+impl Statistics {
+    pub fn entries(&self) -> Vec<(&'static str, u64)> {
+        vec![
+            ("decisions", self.decisions),
+            ("propagations", self.propagations),
+            ("pure_literals", self.pure_literals),
+            ("max_depth", self.max_depth),
+            ("backtracks", self.backtracks),
+        ]
     }
+}
 
-    fn simplify_cnf(cnf: &mut CNF) -> Result<Vec<Literal>, Error> {
-        let mut eval_set = Vec::<Literal>::new();
+enum Status {
+    Sat,
+    Unsat,
+    Interrupted(String),
+}
 
-        match Self::unit_propagation(cnf) {
-            Err(e) => {
-                return Err(e)
-            },
-            Ok(mut eval) => {
-                eval_set.append(&mut eval);
-            }
-        }
+struct Decision {
+    literal: Literal,
+    flipped: bool,  // the other value is refuted already
+    trail_len: usize,
+}
 
-        Self::cnf_normalization(cnf);
+// The formula is never copied: assignments go on a trail and are undone by
+// popping it. Per clause the search counts its true and unassigned literals,
+// so units and conflicts show up as soon as a counter changes. Per literal it
+// counts the unsatisfied clauses containing it, which makes pure literals cheap to spot.
+//
+// Every refuted branch is reported as the clause blocking its decisions,
+// so the lemmas form a tree-like resolution refutation in post-order.
+// An interrupted search refutes nothing, it only unwinds.
+struct Search<'a> {
+    heuristic: &'a dyn BranchingHeuristic,
+    phase: Phase,
+    rng: StdRng,
+
+    clauses: Vec<Vec<Literal>>,
+    occurrences: Vec<Vec<usize>>,
+    true_count: Vec<usize>,
+    unassigned_count: Vec<usize>,
+    active_occurrences: Vec<usize>,
+    unsatisfied: usize,
+
+    assigns: Vec<Option<bool>>,
+    trail: Vec<Literal>,
+    decisions: Vec<Decision>,
+    units: Vec<usize>,
+    pure_candidates: Vec<usize>,
+    conflict: bool,
+
+    proof: Option<&'a mut dyn ProofTracer>,
+    error: Option<io::Error>,
+
+    limits: &'a Limits,
+    usage: Usage,
+
+    statistics: Statistics,
+}
 
-        let mut eval_subset = Self::pure_literal_ellimination(cnf);
-        eval_set.append(&mut eval_subset);
+// What the branching heuristic sees of the search, borrowed from it. Lengths
+// and occurrence counts come from the counters kept up to date on every
+// assignment, so answering them costs no scan.
+struct SearchView<'b> {
+    clauses: &'b [Vec<Literal>],
+    true_count: &'b [usize],
+    unassigned_count: &'b [usize],
+    active_occurrences: &'b [usize],
+    assigns: &'b [Option<bool>],
+}
 
-        return Ok(eval_set);
+impl BranchingView for SearchView<'_> {
+    fn var_count(&self) -> usize {
+        self.assigns.len() - 1
     }
 
-    pub fn solve_dpll(mut cnf: CNF) -> Result<Vec<Literal>, Error> {
-        if Self::has_empty_clause(cnf.get_clauses()) {
-            return Err(Error::new("Unsatisfied"));
-        }
+    fn clause_count(&self) -> usize {
+        self.clauses.len()
+    }
 
-        let mut eval_set = Vec::<Literal>::new();
+    fn clause(&self, c: usize) -> &[Literal] {
+        &self.clauses[c]
+    }
 
-        let mut simplified = true;
-        while simplified {
-            match Self::simplify_cnf(&mut cnf) {
-                Err(e) => {
-                    return Err(e)
-                },
-                Ok(mut eval_subset) => {
-                    if eval_subset.is_empty() {
-                        simplified = false;
-                    }
+    fn is_satisfied(&self, c: usize) -> bool {
+        self.true_count[c] > 0
+    }
 
-                    eval_set.append(&mut eval_subset);
-                }
-            }
+    fn unassigned_len(&self, c: usize) -> usize {
+        // The counter leaves out false literals only, and an unsatisfied clause has no true one.
+        self.unassigned_count[c]
+    }
 
-            if cnf.get_clauses().is_empty() {
-                return Ok(eval_set);
-            }
+    fn value(&self, l: Literal) -> Option<bool> {
+        self.assigns[l.get_var()].map(|v| v != l.is_negative())
+    }
 
-            if Self::has_empty_clause(cnf.get_clauses()) {
-                return Err(Error::new("Unsatisfied"));
-            }
-        }
-        
-        let l;
+    fn occurrences(&self, l: Literal) -> usize {
+        self.active_occurrences[l.index()]
+    }
+}
+
+impl<'a> Search<'a> {
+    fn new(dpll: &'a DPLL, cnf: &CNF, proof: Option<&'a mut dyn ProofTracer>, limits: &'a Limits) -> Search<'a> {
+        let n = cnf.var_count() + 1;
+        let mut search = Search {
+            heuristic: dpll.heuristic.as_ref(),
+            phase: dpll.phase,
+            rng: match dpll.seed {
+                Some(seed) => StdRng::seed_from_u64(seed),
+                None => StdRng::from_entropy(),
+            },
+            clauses: Vec::new(),
+            occurrences: vec![Vec::new(); 2 * n],
+            true_count: Vec::new(),
+            unassigned_count: Vec::new(),
+            active_occurrences: vec![0; 2 * n],
+            unsatisfied: 0,
+            assigns: vec![None; n],
+            trail: Vec::new(),
+            decisions: Vec::new(),
+            units: Vec::new(),
+            pure_candidates: (1..n).collect(),
+            conflict: false,
+            proof,
+            error: None,
+            limits,
+            usage: Usage::default(),
+            statistics: Statistics::default(),
+        };
 
-        match Self::get_any_literal(&mut cnf) {
-            Some(x) => { l = x },
-            None => { return Err(Error::new("Unsatisfied")) }
+        for clause in cnf.clauses().iter() {
+            search.add_clause(clause);
         }
-    
-        let (positive, negative) = Self::eval_on_literal(&mut cnf, l);
+        search
+    }
+
 
-        Self::add_clauses(&mut cnf, &positive);
+    fn value(&self, l: Literal) -> Option<bool> {
+        self.assigns[l.get_var()].map(|v| v != l.is_negative())
+    }
 
-        match Self::solve_dpll(cnf.clone()) {
-            Ok(mut eval_subset) => {
-                eval_set.append(&mut eval_subset);
-                eval_set.push(l.neg());
+    fn add_clause(&mut self, clause: &[Literal]) {
+        if clause.iter().any(|l| clause.contains(&l.neg())) {
+            return;
+        }
 
-                return Ok(eval_set);
-            },
-            Err(_) => {}
+        let c = self.clauses.len();
+        for l in clause.iter() {
+            self.occurrences[l.index()].push(c);
+            self.active_occurrences[l.index()] += 1;
         }
 
-        Self::remove_clauses(&mut cnf, &positive);
-        Self::add_clauses(&mut cnf, &negative);
+        match clause.len() {
+            0 => self.conflict = true,
+            1 => self.units.push(c),
+            _ => {},
+        }
 
-        match Self::solve_dpll(cnf.clone()) {
-            Ok(mut eval_subset) => {
-                eval_set.append(&mut eval_subset);
-                eval_set.push(l);
+        self.clauses.push(clause.to_vec());
+        self.true_count.push(0);
+        self.unassigned_count.push(clause.len());
+        self.unsatisfied += 1;
+    }
 
-                return Ok(eval_set);
-            },
-            Err(_) => {}
+    fn assign(&mut self, l: Literal) {
+        self.assigns[l.get_var()] = Some(!l.is_negative());
+        self.trail.push(l);
+
+        for k in 0..self.occurrences[l.index()].len() {
+            let c = self.occurrences[l.index()][k];
+            self.true_count[c] += 1;
+            if self.true_count[c] == 1 {
+                self.unsatisfied -= 1;
+                for other in self.clauses[c].iter() {
+                    self.active_occurrences[other.index()] -= 1;
+                    if self.active_occurrences[other.index()] == 0 {
+                        self.pure_candidates.push(other.get_var());
+                    }
+                }
+            }
         }
 
-        Err(Error::new("Unsatisfied"))
+        for k in 0..self.occurrences[l.neg().index()].len() {
+            let c = self.occurrences[l.neg().index()][k];
+            self.unassigned_count[c] -= 1;
+            if self.true_count[c] == 0 {
+                match self.unassigned_count[c] {
+                    0 => self.conflict = true,
+                    1 => self.units.push(c),
+                    _ => {},
+                }
+            }
+        }
     }
 
-    fn is_unit_clause(clause: &Vec<Literal>) -> bool {
-        return clause.len() == 1;
+    fn unassign(&mut self) {
+        let l = self.trail.pop().unwrap();
+        self.assigns[l.get_var()] = None;
+
+        for k in 0..self.occurrences[l.index()].len() {
+            let c = self.occurrences[l.index()][k];
+            self.true_count[c] -= 1;
+            if self.true_count[c] == 0 {
+                self.unsatisfied += 1;
+                for other in self.clauses[c].iter() {
+                    self.active_occurrences[other.index()] += 1;
+                }
+            }
+        }
+
+        for k in 0..self.occurrences[l.neg().index()].len() {
+            let c = self.occurrences[l.neg().index()][k];
+            self.unassigned_count[c] += 1;
+        }
     }
 
-    fn unit_propagation(cnf: &mut CNF) -> Result<Vec<Literal>, Error> {
-        let mut unit_clauses = HashSet::<Literal>::new();
-        let clauses: &mut HashSet<Vec<Literal>> = cnf.get_clauses();
+    fn cancel_until(&mut self, trail_len: usize) {
+        while self.trail.len() > trail_len {
+            self.unassign();
+        }
+        self.units.clear();
+        self.pure_candidates.clear();
+        self.conflict = false;
+    }
 
-        for clause in clauses.iter() {
-            if !Self::is_unit_clause(&clause) {
+    // Assigns the unit literals until fixpoint, false on conflict.
+    fn propagate(&mut self) -> bool {
+        while let Some(c) = self.units.pop() {
+            if self.conflict {
+                break;
+            }
+            if self.true_count[c] > 0 {
                 continue;
             }
 
-            if unit_clauses.contains(&clause[0].neg()) {
-                return Err(Error::new("Unsatisfiable"));
+            match self.clauses[c].iter().cloned().find(|l| self.value(*l).is_none()) {
+                Some(l) => {
+                    self.assign(l);
+                    self.statistics.propagations += 1;
+                },
+                None => self.conflict = true,
             }
-
-            unit_clauses.insert(clause[0]);
         }
+        !self.conflict
+    }
 
-        let mut to_remove = Vec::<Vec<Literal>>::new();
-        let mut to_add = Vec::<Vec<Literal>>::new();
-
-        for clause in clauses.iter() {
-            if Self::contains_literal_from_set(clause, &unit_clauses) {
-                to_remove.push(clause.clone());
+    // Assigns every literal whose negation is in no unsatisfied clause.
+    // That only satisfies clauses, so it can not lead to a conflict.
+    fn eliminate_pure_literals(&mut self) {
+        while let Some(v) = self.pure_candidates.pop() {
+            if self.assigns[v].is_some() {
                 continue;
             }
 
-            if !Self::can_resolve_with_set(clause, &unit_clauses) {
+            let positive = self.active_occurrences[2 * v];
+            let negative = self.active_occurrences[2 * v + 1];
+            if positive > 0 && negative == 0 {
+                self.assign(Literal::new(v, false));
+            } else if negative > 0 && positive == 0 {
+                self.assign(Literal::new(v, true));
+            } else {
                 continue;
             }
-
-            to_remove.push(clause.clone());
-            let new_clause = clause
-                .iter()
-                .cloned()
-                .filter(|literal| !unit_clauses.contains(literal) && !unit_clauses.contains(&literal.neg()))
-                .collect();
-
-            to_add.push(new_clause);
+            self.statistics.pure_literals += 1;
         }
+    }
 
-        for clause in to_remove.iter() {
-            clauses.remove(clause);
-        }
+    // The literal to set true first. After propagation every unsatisfied
+    // clause has unassigned literals, the first one is taken if the heuristic has no answer.
+    fn pick_branch_literal(&mut self) -> Literal {
+        let view = SearchView {
+            clauses: &self.clauses,
+            true_count: &self.true_count,
+            unassigned_count: &self.unassigned_count,
+            active_occurrences: &self.active_occurrences,
+            assigns: &self.assigns,
+        };
+        let l = self.heuristic.pick(&view, &mut self.rng)
+            .filter(|l| view.value(*l).is_none())
+            .unwrap_or_else(|| {
+                let c = (0..view.clause_count()).find(|c| !view.is_satisfied(*c)).unwrap();
+                view.clause(c).iter().cloned().find(|l| view.value(*l).is_none()).unwrap()
+            });
+        self.phase.apply(l, &mut self.rng)
+    }
 
-        for clause in to_add.into_iter() {
-            clauses.insert(clause);
-        }
-        
-        return Ok(unit_clauses.into_iter().collect());
+    // Counts a decision, returns the reason to stop if a limit is reached.
+    fn check_limits(&mut self) -> Option<String> {
+        self.usage.decisions += 1;
+        self.limits.check(&mut self.usage)
     }
 
-    fn can_resolve_with_set(clause: &Vec<Literal>, set: &HashSet<Literal>) -> bool {
-        for l in clause.iter() {
-            if set.contains(&l.neg()) {
-                return true;
-            }
-        }
-        false
+    fn decide(&mut self, l: Literal, flipped: bool) {
+        self.decisions.push(Decision { literal: l, flipped, trail_len: self.trail.len() });
+        self.statistics.decisions += 1;
+        self.statistics.max_depth = self.statistics.max_depth.max(self.decisions.len() as u64);
+        self.assign(l);
     }
 
-    fn contains_literal_from_set(clause: &Vec<Literal>, set: &HashSet<Literal>) -> bool {
-        for l in clause.iter() {
-            if set.contains(l) {
-                return true;
-            }
+    fn refute_branch(&mut self) {
+        if self.error.is_some() {
+            return;
+        }
+        if let Some(proof) = self.proof.as_mut() {
+            let clause: Vec<Literal> = self.decisions.iter().map(|d| d.literal.neg()).collect();
+            self.error = proof.add(&clause).err();
         }
-        return false;
     }
 
-    fn cnf_normalization(cnf: &mut CNF) {
-        let clauses: &mut HashSet<Vec<Literal>> = cnf.get_clauses();
-        let mut to_remove = Vec::<Vec<Literal>>::new();
+    // Refutes the current branch and every parent with both values refuted by now,
+    // then flips the deepest decision left. Ok(false) if there is none.
+    fn backtrack(&mut self) -> Result<bool, String> {
+        loop {
+            self.refute_branch();
+
+            let Some(decision) = self.decisions.pop() else {
+                return Ok(false);
+            };
 
-        for clause in clauses.iter() {
-            let mut known_literals = HashSet::<Literal>::new();
+            self.cancel_until(decision.trail_len);
+            self.statistics.backtracks += 1;
 
-            for literal in clause.iter() {
-                if known_literals.contains(&literal.neg()) {
-                    to_remove.push(clause.clone());
-                    break;
+            if !decision.flipped {
+                if let Some(reason) = self.check_limits() {
+                    return Err(reason);
                 }
-                known_literals.insert(literal.clone());
+                self.decide(decision.literal.neg(), true);
+                return Ok(true);
             }
         }
-
-        for c in to_remove.iter() {
-            clauses.remove(c);
-        }
     }
 
-    fn pure_literal_ellimination(cnf: &mut CNF) -> Vec<Literal> {
-        let clauses: &mut HashSet<Vec<Literal>> = cnf.get_clauses();
-
-        let mut known_literals = HashSet::<Literal>::new();
-        let mut not_a_pure_literals = HashSet::<Literal>::new();
-
-        for clause in clauses.iter() {
-            for literal in clause {
-                if known_literals.contains(&literal.neg()) {
-                    not_a_pure_literals.insert(literal.clone());
-                    not_a_pure_literals.insert(literal.neg());
-                } else {
-                    known_literals.insert(literal.clone());
+    fn solve(&mut self) -> Status {
+        loop {
+            if !self.propagate() {
+                match self.backtrack() {
+                    Ok(true) => continue,
+                    Ok(false) => return Status::Unsat,
+                    Err(reason) => return Status::Interrupted(reason),
                 }
             }
-        }
 
-        let mut pure_literals = HashSet::<Literal>::new();
-        
-        for l in known_literals.into_iter() {
-            if !not_a_pure_literals.contains(&l) {
-                pure_literals.insert(l);
+            self.eliminate_pure_literals();
+            if self.unsatisfied == 0 {
+                return Status::Sat;
             }
-        }
 
-        let mut to_remove = Vec::<Vec<Literal>>::new();
-        for clause in clauses.iter() {
-            for literal in clause {
-                if pure_literals.contains(literal) {
-                    to_remove.push(clause.clone());
-                    break;
-                }
+            if let Some(reason) = self.check_limits() {
+                return Status::Interrupted(reason);
             }
-        }
 
-        for clause in to_remove.iter() {
-            clauses.remove(clause);
+            let l = self.pick_branch_literal();
+            self.decide(l, false);
         }
+    }
 
-        pure_literals.into_iter().collect()
+    // Variables left free once every clause is satisfied are taken as false.
+    fn model(&self) -> Assignment {
+        let values = self.assigns.iter()
+            .map(|value| value.or(Some(false)))
+            .collect();
+        Assignment::from_values(values)
     }
 
-    fn remove_literal(clause: &Vec<Literal>, literal: &Literal) -> Vec<Literal> {
-        clause
-            .iter()
-            .cloned()
-            .filter(|l| l != literal)
-            .collect()
+    fn outcome(&self, status: Status) -> Outcome {
+        match status {
+            Status::Sat => Outcome::Sat(self.model()),
+            Status::Unsat => Outcome::Unsat,
+            Status::Interrupted(reason) => Outcome::Unknown(reason),
+        }
     }
+}
 
-    fn eval_on_literal(cnf: &mut CNF, l: Literal) -> (Vec<Vec<Literal>>, Vec<Vec<Literal>>) {
-        let mut have_positive = Vec::<Vec<Literal>>::new();
-        let mut have_negative = Vec::<Vec<Literal>>::new();
+impl Solver for DPLL {
+    fn solve_with_limits(&self, cnf: CNF, limits: &Limits) -> Result<Outcome, Error> {
+        let mut search = Search::new(self, &cnf, None, limits);
+        let status = search.solve();
+        self.statistics.set(search.statistics);
+        Ok(search.outcome(status))
+    }
 
-        let mut to_remove = Vec::<Vec<Literal>>::new();
+    fn statistics(&self) -> Vec<(&'static str, u64)> {
+        self.statistics.get().entries()
+    }
+}
 
-        for clause in cnf.clauses().iter() {
-            if clause.contains(&l) {
-                let new_clause = Self::remove_literal(clause, &l);
-                if !cnf.clauses().contains(&new_clause) {
-                    have_positive.push(new_clause);
-                }
-                
-                to_remove.push(clause.clone());
+impl ProofSolver for DPLL {
+    fn solve_with_proof(&self, cnf: CNF, proof: &mut dyn ProofTracer) -> Result<Outcome, Error> {
+        let limits = Limits::new();
+        let mut search = Search::new(self, &cnf, Some(proof), &limits);
 
-            } else if clause.contains(&l.neg()) {
-                let new_clause = Self::remove_literal(clause, &l.neg());
-                if !cnf.clauses().contains(&new_clause) {
-                    have_negative.push(new_clause);
-                }
-        
-                to_remove.push(clause.clone());
+        let status = search.solve();
+        let outcome = search.outcome(status);
+        self.statistics.set(search.statistics);
 
-            }
+        match search.error {
+            Some(e) => Err(Error::from(e)),
+            None => Ok(outcome),
         }
+    }
+}
 
-        for clause in to_remove.iter() {
-            cnf.get_clauses().remove(clause);
-        }
+impl Default for DPLL {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-        (have_positive, have_negative)
+impl DPLL {
+    pub fn new() -> DPLL {
+        Self::with_heuristic(JeroslowWang::two_sided())
     }
 
-    fn get_any_literal(c: &mut CNF) -> Option<Literal> {
-        for clause in c.get_clauses().iter() {
-            if !clause.is_empty() {
-                return Some(clause[0]);
-            }
+    pub fn with_heuristic<H: BranchingHeuristic + 'static>(heuristic: H) -> DPLL {
+        DPLL {
+            heuristic: Box::new(heuristic),
+            phase: Phase::Heuristic,
+            seed: None,
+            statistics: Cell::new(Statistics::default()),
         }
-        return None;
     }
 
-    fn remove_clauses(c: &mut CNF, clauses: &Vec<Vec<Literal>>) {
-        for clause in clauses.iter() {
-            c.get_clauses().remove(clause);
-        }
+    pub fn set_heuristic<H: BranchingHeuristic + 'static>(&mut self, value: H) {
+        self.heuristic = Box::new(value);
     }
 
-    fn add_clauses(c: &mut CNF, clauses: &Vec<Vec<Literal>>) {
-        for clause in clauses.iter() {
-            c.get_clauses().insert(clause.clone());
-        }
+    pub fn set_phase(&mut self, value: Phase) {
+        self.phase = value;
     }
 
-    fn has_empty_clause(clauses: &HashSet<Vec<Literal>>) -> bool {
-        for c in clauses.iter() {
-            if c.is_empty() {
-                return true;
-            }
-        }
+    pub fn set_seed(&mut self, value: u64) {
+        self.seed = Some(value);
+    }
+
+    // Statistics of the last call to solve.
+    pub fn last_statistics(&self) -> Statistics {
+        self.statistics.get()
+    }
 
-        return false;
+    pub fn solve_dpll(cnf: CNF) -> Option<Assignment> {
+        let dpll = DPLL::new();
+        let limits = Limits::new();
+        let mut search = Search::new(&dpll, &cnf, None, &limits);
+        match search.solve() {
+            Status::Sat => Some(search.model()),
+            _ => None,
+        }
     }
-}
\ No newline at end of file
+}
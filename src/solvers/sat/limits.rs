@@ -0,0 +1,105 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+// Resource bounds shared by all solvers. Clones share the cancellation flag,
+// so a clone handed to a worker can be cancelled from the outside.
+#[derive(Clone, Debug, Default)]
+pub struct Limits {
+    deadline: Option<Instant>,
+    max_decisions: Option<u64>,
+    max_conflicts: Option<u64>,
+    max_flips: Option<u64>,
+    max_derived_literals: Option<usize>,
+    cancelled: Arc<AtomicBool>,
+}
+
+// What a solver has spent so far, compared against the Limits.
+#[derive(Clone, Debug, Default)]
+pub struct Usage {
+    pub decisions: u64,
+    pub conflicts: u64,
+    pub flips: u64,
+    pub derived_literals: usize,
+    checks: u64,
+}
+
+impl Limits {
+    // Reading the clock is comparatively slow, so it only happens every few checks.
+    const CLOCK_PERIOD: u64 = 32;
+
+    pub fn new() -> Limits {
+        Limits::default()
+    }
+
+    pub fn set_deadline(&mut self, value: Instant) {
+        self.deadline = Some(value);
+    }
+
+    // Deadline value from now: calls made after it has passed stop at once.
+    pub fn set_timeout(&mut self, value: Duration) {
+        self.deadline = Some(Instant::now() + value);
+    }
+
+    pub fn set_max_decisions(&mut self, value: u64) {
+        self.max_decisions = Some(value);
+    }
+
+    pub fn set_max_conflicts(&mut self, value: u64) {
+        self.max_conflicts = Some(value);
+    }
+
+    pub fn set_max_flips(&mut self, value: u64) {
+        self.max_flips = Some(value);
+    }
+
+    pub fn set_max_derived_literals(&mut self, value: usize) {
+        self.max_derived_literals = Some(value);
+    }
+
+    pub fn set_cancel_token(&mut self, value: Arc<AtomicBool>) {
+        self.cancelled = value;
+    }
+
+    pub fn cancel_token(&self) -> Arc<AtomicBool> {
+        self.cancelled.clone()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    // Returns the reason to stop, if any of the limits is reached.
+    pub fn check(&self, usage: &mut Usage) -> Option<String> {
+        if self.is_cancelled() {
+            return Some("cancelled".to_string());
+        }
+
+        if self.max_decisions.is_some_and(|max| usage.decisions > max) {
+            return Some(format!("decision limit of {} reached", self.max_decisions.unwrap()));
+        }
+
+        if self.max_conflicts.is_some_and(|max| usage.conflicts > max) {
+            return Some(format!("conflict limit of {} reached", self.max_conflicts.unwrap()));
+        }
+
+        if self.max_flips.is_some_and(|max| usage.flips > max) {
+            return Some(format!("flip limit of {} reached", self.max_flips.unwrap()));
+        }
+
+        if self.max_derived_literals.is_some_and(|max| usage.derived_literals > max) {
+            return Some(format!("clause growth limit of {} literals reached", self.max_derived_literals.unwrap()));
+        }
+
+        usage.checks += 1;
+        if usage.checks % Self::CLOCK_PERIOD == 1 && self.deadline.is_some_and(|d| Instant::now() >= d) {
+            return Some("time limit reached".to_string());
+        }
+
+        None
+    }
+}
@@ -0,0 +1,341 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+pub use super::solver::{Solver, Outcome, Error};
+use super::limits::{Limits, Usage};
+use crate::p::assignment::Assignment;
+use crate::p::cnf::{CNF, Literal};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BreakFunction {
+    Polynomial { cb: f64, eps: f64 },
+    Exponential { cb: f64 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Heuristic {
+    WalkSAT { noise: f64 },
+    ProbSAT(BreakFunction),
+    GSAT,
+}
+
+#[derive(Clone)]
+pub struct LocalSearch {
+    heuristic: Heuristic,
+    max_flips: usize,
+    max_tries: usize,
+    seed: Option<u64>,
+}
+
+struct State {
+    clauses: Vec<Vec<Literal>>,
+    occurrences: Vec<Vec<usize>>,
+    variables: Vec<usize>,
+
+    assignment: Vec<bool>,
+    true_count: Vec<usize>,
+    critical: Vec<usize>,
+    make: Vec<usize>,
+    breaks: Vec<usize>,
+
+    unsat: Vec<usize>,
+    unsat_position: Vec<Option<usize>>,
+}
+
+impl Solver for LocalSearch {
+    fn solve_with_limits(&self, cnf: CNF, limits: &Limits) -> Result<Outcome, Error> {
+        self.solve_local_search(&cnf, limits)
+    }
+}
+
+impl Default for LocalSearch {
+    fn default() -> Self {
+        Self::new(Heuristic::ProbSAT(BreakFunction::Polynomial { cb: 2.38, eps: 1.0 }))
+    }
+}
+
+impl LocalSearch {
+    pub fn new(heuristic: Heuristic) -> LocalSearch {
+        LocalSearch {
+            heuristic,
+            max_flips: 100000,
+            max_tries: 10,
+            seed: None,
+        }
+    }
+
+    pub fn walksat(noise: f64) -> LocalSearch {
+        Self::new(Heuristic::WalkSAT { noise })
+    }
+
+    pub fn probsat(break_function: BreakFunction) -> LocalSearch {
+        Self::new(Heuristic::ProbSAT(break_function))
+    }
+
+    pub fn gsat() -> LocalSearch {
+        Self::new(Heuristic::GSAT)
+    }
+
+    pub fn set_heuristic(&mut self, value: Heuristic) {
+        self.heuristic = value;
+    }
+
+    pub fn set_max_flips(&mut self, value: usize) {
+        self.max_flips = value;
+    }
+
+    pub fn set_max_tries(&mut self, value: usize) {
+        self.max_tries = value;
+    }
+
+    pub fn set_seed(&mut self, value: u64) {
+        self.seed = Some(value);
+    }
+
+    // Probabilities and break functions that can't be sampled from are
+    // rejected, rather than panicking or picking from NaN weights.
+    fn check_heuristic(&self) -> Result<(), Error> {
+        let valid = match self.heuristic {
+            Heuristic::WalkSAT { noise } => (0.0..=1.0).contains(&noise),
+            Heuristic::ProbSAT(BreakFunction::Polynomial { cb, eps }) => cb.is_finite() && eps.is_finite() && eps > 0.0,
+            Heuristic::ProbSAT(BreakFunction::Exponential { cb }) => cb.is_finite() && cb > 0.0,
+            Heuristic::GSAT => true,
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(Error::InvalidInput(format!("invalid local search heuristic {:?}", self.heuristic)))
+        }
+    }
+
+    pub fn solve_local_search(&self, cnf: &CNF, limits: &Limits) -> Result<Outcome, Error> {
+        self.check_heuristic()?;
+        if cnf.clauses().iter().any(|c| c.is_empty()) {
+            return Ok(Outcome::Unsat);
+        }
+
+        let mut rng = match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+
+        let mut state = State::new(cnf);
+        let mut usage = Usage::default();
+
+        for _try in 0..self.max_tries {
+            state.randomize(&mut rng);
+
+            for _flip in 0..self.max_flips {
+                if state.unsat.is_empty() {
+                    break;
+                }
+
+                usage.flips += 1;
+                if let Some(reason) = limits.check(&mut usage) {
+                    return Ok(Outcome::Unknown(reason));
+                }
+
+                let v = match self.heuristic {
+                    Heuristic::WalkSAT { noise } => state.pick_walksat(&mut rng, noise),
+                    Heuristic::ProbSAT(f) => state.pick_probsat(&mut rng, f),
+                    Heuristic::GSAT => state.pick_gsat(&mut rng),
+                };
+                state.flip(v);
+            }
+
+            if state.unsat.is_empty() {
+                return Ok(Outcome::Sat(state.model()));
+            }
+        }
+
+        Ok(Outcome::Unknown(format!(
+            "no model found within {} tries of {} flips", self.max_tries, self.max_flips
+        )))
+    }
+}
+
+impl State {
+
+    fn new(cnf: &CNF) -> State {
+        let n = cnf.var_count() + 1;
+        let mut clauses = Vec::new();
+        let mut occurrences = vec![Vec::new(); 2 * n];
+
+        for clause in cnf.clauses().iter() {
+            if clause.iter().any(|l| clause.contains(&l.neg())) {
+                continue;
+            }
+
+            for l in clause.iter() {
+                occurrences[l.index()].push(clauses.len());
+            }
+            clauses.push(clause.to_vec());
+        }
+
+        let variables = (1..n).collect();
+        let m = clauses.len();
+
+        State {
+            clauses,
+            occurrences,
+            variables,
+            assignment: vec![false; n],
+            true_count: vec![0; m],
+            critical: vec![0; m],
+            make: vec![0; n],
+            breaks: vec![0; n],
+            unsat: Vec::new(),
+            unsat_position: vec![None; m],
+        }
+    }
+
+    fn is_true(&self, l: Literal) -> bool {
+        self.assignment[l.get_var()] != l.is_negative()
+    }
+
+    fn randomize(&mut self, rng: &mut StdRng) {
+        for v in self.variables.iter() {
+            self.assignment[*v] = rng.gen();
+        }
+
+        self.make.iter_mut().for_each(|x| *x = 0);
+        self.breaks.iter_mut().for_each(|x| *x = 0);
+        self.unsat.clear();
+        self.unsat_position.iter_mut().for_each(|x| *x = None);
+
+        for c in 0..self.clauses.len() {
+            let mut count = 0;
+            for l in self.clauses[c].iter() {
+                if self.is_true(*l) {
+                    count += 1;
+                    self.critical[c] = l.get_var();
+                }
+            }
+            self.true_count[c] = count;
+
+            match count {
+                0 => self.mark_unsat(c),
+                1 => self.breaks[self.critical[c]] += 1,
+                _ => {}
+            }
+        }
+    }
+
+    fn mark_unsat(&mut self, c: usize) {
+        self.unsat_position[c] = Some(self.unsat.len());
+        self.unsat.push(c);
+        for l in self.clauses[c].iter() {
+            self.make[l.get_var()] += 1;
+        }
+    }
+
+    fn mark_sat(&mut self, c: usize) {
+        let position = self.unsat_position[c].take().unwrap();
+        self.unsat.swap_remove(position);
+        if position < self.unsat.len() {
+            self.unsat_position[self.unsat[position]] = Some(position);
+        }
+        for l in self.clauses[c].iter() {
+            self.make[l.get_var()] -= 1;
+        }
+    }
+
+    // Flips the variable, keeping make/break scores and the unsat list up to date.
+    fn flip(&mut self, v: usize) {
+        self.assignment[v] = !self.assignment[v];
+        let became_true = Literal::new(v, !self.assignment[v]);
+        let became_false = became_true.neg();
+
+        for k in 0..self.occurrences[became_true.index()].len() {
+            let c = self.occurrences[became_true.index()][k];
+            self.true_count[c] += 1;
+            match self.true_count[c] {
+                1 => {
+                    self.mark_sat(c);
+                    self.critical[c] = v;
+                    self.breaks[v] += 1;
+                },
+                2 => self.breaks[self.critical[c]] -= 1,
+                _ => {}
+            }
+        }
+
+        for k in 0..self.occurrences[became_false.index()].len() {
+            let c = self.occurrences[became_false.index()][k];
+            self.true_count[c] -= 1;
+            match self.true_count[c] {
+                0 => {
+                    self.breaks[v] -= 1;
+                    self.mark_unsat(c);
+                },
+                1 => {
+                    let w = self.clauses[c].iter().find(|l| self.is_true(**l)).unwrap().get_var();
+                    self.critical[c] = w;
+                    self.breaks[w] += 1;
+                },
+                _ => {}
+            }
+        }
+    }
+
+    fn random_unsat_clause(&self, rng: &mut StdRng) -> usize {
+        self.unsat[rng.gen_range(0..self.unsat.len())]
+    }
+
+    fn pick_walksat(&self, rng: &mut StdRng, noise: f64) -> usize {
+        let clause = &self.clauses[self.random_unsat_clause(rng)];
+
+        let min_break = clause.iter().map(|l| self.breaks[l.get_var()]).min().unwrap();
+        if min_break > 0 && rng.gen_bool(noise) {
+            return clause[rng.gen_range(0..clause.len())].get_var();
+        }
+
+        let best: Vec<usize> = clause.iter()
+            .map(|l| l.get_var())
+            .filter(|v| self.breaks[*v] == min_break)
+            .collect();
+        best[rng.gen_range(0..best.len())]
+    }
+
+    fn pick_probsat(&self, rng: &mut StdRng, f: BreakFunction) -> usize {
+        let clause = &self.clauses[self.random_unsat_clause(rng)];
+
+        let weights: Vec<f64> = clause.iter()
+            .map(|l| {
+                let b = self.breaks[l.get_var()] as f64;
+                match f {
+                    BreakFunction::Polynomial { cb, eps } => (eps + b).powf(-cb),
+                    BreakFunction::Exponential { cb } => cb.powf(-b),
+                }
+            })
+            .collect();
+
+        let mut r = rng.gen::<f64>() * weights.iter().sum::<f64>();
+        for (l, w) in clause.iter().zip(weights.iter()) {
+            if r < *w {
+                return l.get_var();
+            }
+            r -= w;
+        }
+        clause[clause.len() - 1].get_var()
+    }
+
+    fn pick_gsat(&self, rng: &mut StdRng) -> usize {
+        let score = |v: usize| self.make[v] as i64 - self.breaks[v] as i64;
+
+        let best_score = self.variables.iter().map(|v| score(*v)).max().unwrap();
+        let best: Vec<usize> = self.variables.iter()
+            .cloned()
+            .filter(|v| score(*v) == best_score)
+            .collect();
+        best[rng.gen_range(0..best.len())]
+    }
+
+    fn model(&self) -> Assignment {
+        let mut values = vec![None; self.assignment.len()];
+        for v in self.variables.iter() {
+            values[*v] = Some(self.assignment[*v]);
+        }
+        Assignment::from_values(values)
+    }
+}
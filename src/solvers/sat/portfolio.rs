@@ -0,0 +1,131 @@
+use std::cell::Cell;
+use std::sync::atomic::AtomicBool;
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+pub use super::solver::{Solver, Outcome, Error};
+use super::limits::Limits;
+use crate::p::cnf::CNF;
+
+// Runs several solvers on the same CNF, each on its own thread, and answers
+// with the first Sat or Unsat. The other workers are then cancelled through
+// their limits, which share a cancellation flag. Solvers keep their
+// statistics in a Cell, so they cannot be shared between threads: every
+// worker builds its own from a factory, on its own thread.
+pub struct Portfolio {
+    factories: Vec<Box<dyn Fn() -> Box<dyn Solver> + Send + Sync>>,
+    statistics: Cell<Statistics>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Statistics {
+    pub workers: u64,
+    pub winner: Option<usize>,  // index of the solver that answered, in the order of add
+}
+
+impl Statistics {
+    pub fn entries(&self) -> Vec<(&'static str, u64)> {
+        let mut entries = vec![("workers", self.workers)];
+        if let Some(winner) = self.winner {
+            entries.push(("winner", winner as u64));
+        }
+        entries
+    }
+}
+
+// How often the caller's cancellation is passed on to the workers.
+const POLL_PERIOD: Duration = Duration::from_millis(10);
+
+impl Default for Portfolio {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Solver for Portfolio {
+    fn solve_with_limits(&self, cnf: CNF, limits: &Limits) -> Result<Outcome, Error> {
+        if self.factories.is_empty() {
+            return Err(Error::InvalidInput("the portfolio has no solvers".to_string()));
+        }
+
+        // The workers get their own flag, so cancelling them leaves the caller's untouched.
+        let mut worker_limits = limits.clone();
+        worker_limits.set_cancel_token(Arc::new(AtomicBool::new(false)));
+
+        let (sender, receiver) = mpsc::channel();
+        let (winner, outcome) = thread::scope(|scope| {
+            for (i, factory) in self.factories.iter().enumerate() {
+                let (sender, cnf, limits) = (sender.clone(), cnf.clone(), worker_limits.clone());
+                scope.spawn(move || {
+                    let outcome = factory().solve_with_limits(cnf, &limits);
+                    // The receiver is gone once an answer was taken.
+                    let _ = sender.send((i, outcome));
+                });
+            }
+            drop(sender);
+
+            let mut reasons = Vec::new();
+            let mut error = None;
+            loop {
+                match receiver.recv_timeout(POLL_PERIOD) {
+                    Ok((i, Ok(outcome @ (Outcome::Sat(_) | Outcome::Unsat)))) => {
+                        worker_limits.cancel();
+                        return (Some(i), Ok(outcome));
+                    },
+                    Ok((i, Ok(Outcome::Unknown(reason)))) => reasons.push(format!("solver {}: {}", i, reason)),
+                    Ok((_, Err(e))) => error = error.or(Some(e)),
+                    Err(mpsc::RecvTimeoutError::Timeout) => {
+                        if limits.is_cancelled() {
+                            worker_limits.cancel();
+                        }
+                    },
+                    Err(mpsc::RecvTimeoutError::Disconnected) => break,
+                }
+            }
+
+            match (reasons.is_empty(), error) {
+                (true, Some(e)) => (None, Err(e)),
+                _ => (None, Ok(Outcome::Unknown(reasons.join("; ")))),
+            }
+        });
+
+        self.statistics.set(Statistics {
+            workers: self.factories.len() as u64,
+            winner,
+        });
+        outcome
+    }
+
+    fn statistics(&self) -> Vec<(&'static str, u64)> {
+        self.last_statistics().entries()
+    }
+}
+
+impl Portfolio {
+    pub fn new() -> Portfolio {
+        Portfolio {
+            factories: Vec::new(),
+            statistics: Cell::new(Statistics::default()),
+        }
+    }
+
+    // Adds a worker, whose solver the factory builds for every call, e.g.
+    // `add(CDCL::new)` or a closure setting a seed or parameters.
+    pub fn add<S: Solver + 'static, F: Fn() -> S + Send + Sync + 'static>(&mut self, factory: F) {
+        self.factories.push(Box::new(move || Box::new(factory())));
+    }
+
+    pub fn len(&self) -> usize {
+        self.factories.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.factories.is_empty()
+    }
+
+    // Statistics of the last call to solve.
+    pub fn last_statistics(&self) -> Statistics {
+        self.statistics.get()
+    }
+}
@@ -1,11 +1,19 @@
-use std::collections::{HashSet, HashMap};
+use std::cell::Cell;
 use std::{mem::swap};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
 
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
-use rand::{distributions::Uniform, Rng};
+use rand::{distributions::Uniform, Rng, SeedableRng};
 
-pub use super::solver::{Solver, Error};
+pub use super::solver::{Solver, Outcome, Error};
+use super::limits::{Limits, Usage};
+use crate::p::assignment::Assignment;
 use crate::p::cnf::{CNF, Literal};
+use crate::p::collections::{HashSet, HashMap};
 
 #[derive(Clone)]
 pub struct PPSZ {
@@ -14,10 +22,38 @@ pub struct PPSZ {
     max_search_iterations: usize,
     max_clause_size: usize,
     bounded_resolve_iterations: usize,
+    seed: Option<u64>,
+    threads: usize,
 
-    potential_resolve_clauses_with_literal: HashMap<Literal, HashSet<usize>>
+    potential_resolve_clauses_with_literal: HashMap<Literal, HashSet<usize>>,
+
+    statistics: Cell<Statistics>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Statistics {
+    pub resolve_iterations: u64,
+    pub resolvents: u64,  // new clauses added by bounded_resolve
+    pub forced: u64,  // assignments in modify taken from a unit clause
+    pub guessed: u64,  // random assignments in modify
+    pub search_iterations: u64,
 }
 
+impl Statistics {
+    pub fn entries(&self) -> Vec<(&'static str, u64)> {
+        vec![
+            ("resolve_iterations", self.resolve_iterations),
+            ("resolvents", self.resolvents),
+            ("forced", self.forced),
+            ("guessed", self.guessed),
+            ("search_iterations", self.search_iterations),
+        ]
+    }
+}
+
+// How often the caller's cancellation is passed on to the search workers.
+const POLL_PERIOD: Duration = Duration::from_millis(10);
+
 #[derive(Clone)]
 struct ExtendedCNF {
     clauses_set: HashSet<Vec<Literal>>,
@@ -29,12 +65,12 @@ struct ExtendedCNF {
 }
 
 impl ExtendedCNF {
-    pub fn from_cnf(cnf: &mut CNF) -> ExtendedCNF {
+    pub fn from_cnf(cnf: &CNF) -> ExtendedCNF {
         let mut clauses = Vec::<Vec<Literal>>::new();
-        let mut clauses_with_literal = HashMap::<Literal, HashSet<usize>>::new();
-        let mut unit_clauses = HashSet::<Literal>::new();
+        let mut clauses_with_literal = HashMap::<Literal, HashSet<usize>>::default();
+        let mut unit_clauses = HashSet::<Literal>::default();
 
-        for c in cnf.get_clauses().iter() {
+        for c in cnf.clauses().iter() {
             if c.len() == 1 {
                 unit_clauses.insert(c[0]);
                 continue;
@@ -42,28 +78,28 @@ impl ExtendedCNF {
 
             for l in c.iter() {
                 clauses_with_literal.entry(*l)
-                    .or_insert(HashSet::new())
+                    .or_default()
                     .insert(clauses.len());
             }
 
-            clauses.push(c.clone());
+            clauses.push(c.to_vec());
         }
 
         let total_clauses = clauses.len() + unit_clauses.len();
 
         ExtendedCNF {
-            clauses_set: cnf.get_clauses().clone(),
-            clauses: clauses,
-            clauses_with_literal: clauses_with_literal,
-            total_clauses: total_clauses,
+            clauses_set: cnf.clauses().iter().map(|c| c.to_vec()).collect(),
+            clauses,
+            clauses_with_literal,
+            total_clauses,
             unsatisified_clauses: total_clauses,
-            unit_clauses: unit_clauses,
+            unit_clauses,
         }
     }
 
-    pub fn apply(&mut self, l: Literal) -> Option<Error> {
+    pub fn apply(&mut self, l: Literal) -> bool {
         if self.unit_clauses.contains(&l.neg()) {
-            return Some(Error::new("Unsatisfiable"));
+            return false;
         }
 
         self.unsatisified_clauses -= self.unit_clauses.remove(&l) as usize;
@@ -72,15 +108,15 @@ impl ExtendedCNF {
         let mut to_add = Vec::<(Literal, usize)>::new();
 
         let clauses_containing_l = self.clauses_with_literal
-            .entry(l.clone())
-            .or_insert(HashSet::new())
+            .entry(l)
+            .or_default()
             .iter();
 
         for c in clauses_containing_l {
             let clause = &self.clauses[*c];
 
             for literal in clause.iter() {
-                to_remove.push((literal.clone(), *c));
+                to_remove.push((*literal, *c));
             }
 
             self.unsatisified_clauses -= 1;
@@ -88,14 +124,14 @@ impl ExtendedCNF {
 
         let clauses_containing_not_l = self.clauses_with_literal
             .entry(l.neg())
-            .or_insert(HashSet::new())
+            .or_default()
             .iter();
 
         for c in clauses_containing_not_l {
             let clause = self.clauses[*c].clone();
 
             for literal in clause.iter() {
-                to_remove.push((literal.clone(), *c));
+                to_remove.push((*literal, *c));
             }
 
             let resolved_clause = Self::clause_without_literal(clause, l);
@@ -127,14 +163,22 @@ impl ExtendedCNF {
             self.get_clauses_with(l).insert(index);
         }
 
-        None
+        true
+    }
+
+    pub fn is_refuted(&self) -> bool {
+        if self.clauses_set.contains(&Vec::new()) {
+            return true;
+        }
+
+        self.unit_clauses.iter().any(|l| self.unit_clauses.contains(&l.neg()))
     }
 
     pub fn get_literals(&mut self) -> Vec<Literal> {
-        let mut literals = HashSet::<Literal>::new();
+        let mut literals = HashSet::<Literal>::default();
 
-        for l in self.clauses_with_literal.keys().into_iter() {
-            let literal = l.clone();
+        for l in self.clauses_with_literal.keys() {
+            let literal = *l;
 
             literals.insert(literal);
             literals.insert(literal.neg());
@@ -153,7 +197,7 @@ impl ExtendedCNF {
     pub fn get_clauses_with(&mut self, l: Literal) -> &mut HashSet<usize> {
         self.clauses_with_literal
             .entry(l)
-            .or_insert(HashSet::new())
+            .or_default()
     }
 
     pub fn clauses_with(&self, l: Literal) -> Option<&HashSet<usize>> {
@@ -183,24 +227,36 @@ impl ExtendedCNF {
 
         for l in clause.iter() {
             let index = self.clauses.len();
-            self.get_clauses_with(l.clone()).insert(index);
+            self.get_clauses_with(*l).insert(index);
         }
 
         self.clauses.push(clause.clone());
         self.clauses_set.insert(clause);
 
-        return true;
+        true
     }
 }
 
 impl Solver for PPSZ {
-    fn solve(&self, cnf: CNF) -> Result<Vec<Literal>, Error> {
+    fn solve_with_limits(&self, cnf: CNF, limits: &Limits) -> Result<Outcome, Error> {
         let mut s = (*self).clone();
 
         let n = cnf.var_count();
         s.max_clauses = n * n * n / 5;  // Magic constant here
 
-        Self::solve_ppsz(&mut s, cnf)
+        let outcome = Self::solve_ppsz(&mut s, cnf, limits);
+        self.statistics.set(s.statistics.get());
+        outcome
+    }
+
+    fn statistics(&self) -> Vec<(&'static str, u64)> {
+        self.statistics.get().entries()
+    }
+}
+
+impl Default for PPSZ {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -212,11 +268,20 @@ impl PPSZ {
             max_search_iterations: 1000,
             max_clause_size: 3,
             bounded_resolve_iterations: 2,
+            seed: None,
+            threads: 1,
+
+            potential_resolve_clauses_with_literal: HashMap::<Literal, HashSet<usize>>::default(),
 
-            potential_resolve_clauses_with_literal: HashMap::<Literal, HashSet<usize>>::new()
+            statistics: Cell::new(Statistics::default()),
         }
     }
 
+    // Statistics of the last call to solve.
+    pub fn last_statistics(&self) -> Statistics {
+        self.statistics.get()
+    }
+
     pub fn set_max_clauses(&mut self, value: usize) {
         self.max_clauses = value;
     }
@@ -237,29 +302,117 @@ impl PPSZ {
         self.bounded_resolve_iterations = value;
     }
 
-    pub fn solve_ppsz(&mut self, mut cnf: CNF) -> Result<Vec<Literal>, Error> {
-        let mut ext_cnf = ExtendedCNF::from_cnf(&mut cnf);
+    pub fn set_seed(&mut self, value: u64) {
+        self.seed = Some(value);
+    }
+
+    // Search iterations are spread over this many threads, each with its own
+    // random generator seeded from the main one.
+    pub fn set_threads(&mut self, value: usize) {
+        self.threads = value.max(1);
+    }
+
+    pub fn solve_ppsz(&mut self, cnf: CNF, limits: &Limits) -> Result<Outcome, Error> {
+        let mut ext_cnf = ExtendedCNF::from_cnf(&cnf);
+        let mut usage = Usage::default();
+        let mut statistics = Statistics::default();
 
         self.potential_resolve_clauses_with_literal = ext_cnf.clauses_with_literal.clone();
 
         for _i in 0..self.max_resolve_iterations {
-            self.bounded_resolve(&mut ext_cnf, self.max_clause_size);
+            self.bounded_resolve(&mut ext_cnf, self.max_clause_size, &mut usage, &mut statistics);
+            statistics.resolve_iterations += 1;
 
-            if ext_cnf.unsatisified_clauses > self.max_clauses {
+            if ext_cnf.is_refuted() || ext_cnf.unsatisified_clauses > self.max_clauses {
                 break;
             }
+
+            if let Some(reason) = limits.check(&mut usage) {
+                self.statistics.set(statistics);
+                return Ok(Outcome::Unknown(reason));
+            }
+        }
+
+        if ext_cnf.is_refuted() {
+            self.statistics.set(statistics);
+            return Ok(Outcome::Unsat);
         }
 
-        match Self::search(&mut cnf, &mut ext_cnf, self.max_search_iterations) {
-            None => {},
-            Some(eval_set) => {
-                return Ok(eval_set);
+        let mut rng = match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+
+        let outcome = if self.threads == 1 {
+            Self::search(&cnf, &ext_cnf, self.max_search_iterations, &mut rng, limits, &mut usage, &mut statistics)
+        } else {
+            self.search_parallel(&cnf, &ext_cnf, &mut rng, limits, &mut usage, &mut statistics)
+        };
+        self.statistics.set(statistics);
+        Ok(outcome)
+    }
+
+    // The iterations are split between the workers. Their limits share a
+    // cancellation flag of their own, raised by the first model found or by
+    // the caller's cancellation. Limits on decisions apply to each worker.
+    fn search_parallel(&self, g: &CNF, ext_g: &ExtendedCNF, rng: &mut StdRng, limits: &Limits, usage: &mut Usage, statistics: &mut Statistics) -> Outcome {
+        let (iterations, threads) = (self.max_search_iterations, self.threads);
+        let mut worker_limits = limits.clone();
+        worker_limits.set_cancel_token(Arc::new(AtomicBool::new(false)));
+        let seeds: Vec<u64> = (0..threads).map(|_| rng.gen()).collect();
+
+        let results: Vec<(Outcome, Usage, Statistics)> = thread::scope(|scope| {
+            let workers: Vec<_> = seeds.into_iter().enumerate()
+                .map(|(i, seed)| {
+                    let (limits, mut usage) = (&worker_limits, usage.clone());
+                    // The first iterations % threads workers take one more.
+                    let share = iterations / threads + (i < iterations % threads) as usize;
+                    scope.spawn(move || {
+                        let mut rng = StdRng::seed_from_u64(seed);
+                        let mut statistics = Statistics::default();
+                        let outcome = Self::search(g, ext_g, share, &mut rng, limits, &mut usage, &mut statistics);
+                        if outcome.is_sat() {
+                            limits.cancel();
+                        }
+                        (outcome, usage, statistics)
+                    })
+                })
+                .collect();
+
+            while !workers.iter().all(|worker| worker.is_finished()) {
+                if limits.is_cancelled() {
+                    worker_limits.cancel();
+                }
+                thread::sleep(POLL_PERIOD);
+            }
+            workers.into_iter().map(|worker| worker.join().unwrap()).collect()
+        });
+
+        let mut model = None;
+        let mut reason = None;
+        for (outcome, worker_usage, worker_statistics) in results {
+            usage.decisions += worker_usage.decisions;
+            statistics.forced += worker_statistics.forced;
+            statistics.guessed += worker_statistics.guessed;
+            statistics.search_iterations += worker_statistics.search_iterations;
+            match outcome {
+                Outcome::Sat(u) => model = model.or(Some(u)),
+                Outcome::Unknown(r) => reason = reason.or(Some(r)),
+                Outcome::Unsat => {},
             }
         }
-        return Err(Error::new("Unsatisfiable"));
+
+        match model {
+            Some(u) => Outcome::Sat(u),
+            // A limit, unless every worker went through its share.
+            None => Outcome::Unknown(match reason {
+                Some(r) if (statistics.search_iterations as usize) < iterations => r,
+                _ => format!("gave up after {} search iterations", iterations),
+            }),
+        }
     }
 
-    fn bounded_resolve(&mut self, g: &mut ExtendedCNF, s: usize) {
+    fn bounded_resolve(&mut self, g: &mut ExtendedCNF, s: usize, usage: &mut Usage, statistics: &mut Statistics) {
         let literals = g.get_literals();
 
         let mut new_clauses = Vec::<Vec<Literal>>::new();
@@ -312,16 +465,16 @@ impl PPSZ {
             if clause.len() > 1 {
                 for l in clause.iter() {
                     self.potential_resolve_clauses_with_literal
-                        .entry(l.clone())
-                        .or_insert(HashSet::new())
+                        .entry(*l)
+                        .or_default()
                         .insert(g.clauses.len());
                 }
             }
             
+            usage.derived_literals += clause.len();
+            statistics.resolvents += 1;
             g.add_clause(clause.clone());
         }
-
-        ()
     }
 
     fn resolve(mut a: Vec<Literal>, mut b: Vec<Literal>, l: Literal) -> Option<Vec<Literal>> {
@@ -340,7 +493,7 @@ impl PPSZ {
         Some(result)
     }
 
-    fn is_taut(a: &Vec<Literal>) -> bool {
+    fn is_taut(a: &[Literal]) -> bool {
         for i in a.iter() {
             for j in a.iter() {
                 if i == &j.neg() {
@@ -349,57 +502,66 @@ impl PPSZ {
             }
         }
 
-        return false;
+        false
     }
 
     fn remove_literal(clause: Vec<Literal>, literal: Literal) -> Vec<Literal> {
         clause.iter().cloned().filter(|l| l != &literal).collect()
     }
 
-    fn search(g: &mut CNF, ext_g: &mut ExtendedCNF, iterations: usize) -> Option<Vec<Literal>> {
+    fn search(g: &CNF, ext_g: &ExtendedCNF, iterations: usize, rng: &mut StdRng, limits: &Limits, usage: &mut Usage, statistics: &mut Statistics) -> Outcome {
         for _it in 1..=iterations {
+            if let Some(reason) = limits.check(usage) {
+                return Outcome::Unknown(reason);
+            }
+            statistics.search_iterations += 1;
+
             let mut pi: Vec<usize> = (1..=g.var_count()).collect();
-            pi.shuffle(&mut rand::thread_rng());
+            pi.shuffle(rng);
 
             let y: Vec<bool> = (1..=g.var_count())
-                .map(|_| rand::thread_rng().sample(&Uniform::new(0, 2)) == 1)
+                .map(|_| rng.sample(Uniform::new(0, 2)) == 1)
                 .collect();
 
-            let u = Self::modify(ext_g.clone(), &pi, &y);
-            
-            if g.eval(u.clone()) {
-                return Some(u);
+            let u = Self::modify(ext_g.clone(), &pi, &y, usage, statistics);
+
+            if g.eval(&u).is_satisfied() {
+                return Outcome::Sat(u);
             }
         }
 
-        None
+        Outcome::Unknown(format!("gave up after {} search iterations", iterations))
     }
 
-    fn modify(mut g: ExtendedCNF, pi: &Vec<usize>, y: &Vec<bool>) -> Vec<Literal> {
+    // Every variable not forced by a unit clause counts as a decision.
+    fn modify(mut g: ExtendedCNF, pi: &[usize], y: &[bool], usage: &mut Usage, statistics: &mut Statistics) -> Assignment {
         let n = pi.len();
-        let mut eval_set = Vec::<Literal>::new();
+        let mut values = vec![None; n + 1];
 
         for i in 0..n {
             let var = pi[i] as i32;
 
-            let literal;
-            match Self::check_for_unit_clause(&mut g, Literal::from_int(var)) {
+            let literal = match Self::check_for_unit_clause(&mut g, Literal::from_int(var)) {
                 Some(true) => {
-                    literal = Literal::from_int(var);
+                    statistics.forced += 1;
+                    Literal::from_int(var)
                 },
                 Some(false) => {
-                    literal = Literal::from_int(-var);
+                    statistics.forced += 1;
+                    Literal::from_int(-var)
                 },
                 None => {
-                    literal = Literal{ var: var as usize, sign: !y[i] };
-                }
-            }
+                    usage.decisions += 1;
+                    statistics.guessed += 1;
+                    Literal::new(var as usize, !y[i])
+                },
+            };
 
             g.apply(literal);
-            eval_set.push(literal);
+            values[literal.get_var()] = Some(!literal.is_negative());
         }
 
-        eval_set
+        Assignment::from_values(values)
     }
 
     fn check_for_unit_clause(g: &mut ExtendedCNF, l: Literal) -> Option<bool> {
@@ -1,21 +1,163 @@
+use crate::p::assignment::Assignment;
 use crate::p::cnf::{CNF, Literal};
+use crate::proof::drat::ProofTracer;
+pub use super::limits::Limits;
 
-pub struct Error {
-    what: String
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Sat(Assignment),  // a value for every variable 1..=var_count of the CNF
+    Unsat,
+    Unknown(String),
 }
 
-impl Error {
-    pub fn new(s: &str) -> Error {
-        Error {
-            what: s.to_string()
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    InvalidInput(String),
+    ResourceExhausted(String),
+    Io(String),
+}
+
+impl Outcome {
+    pub fn is_sat(&self) -> bool {
+        matches!(self, Outcome::Sat(_))
+    }
+
+    pub fn is_unsat(&self) -> bool {
+        matches!(self, Outcome::Unsat)
+    }
+
+    pub fn model(&self) -> Option<&Assignment> {
+        match self {
+            Outcome::Sat(model) => Some(model),
+            _ => None,
         }
     }
+}
 
-    pub fn what(&self) -> &str {
-        &self.what
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::InvalidInput(what) => write!(f, "invalid input: {}", what),
+            Error::ResourceExhausted(what) => write!(f, "resource exhausted: {}", what),
+            Error::Io(what) => write!(f, "io error: {}", what),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Error {
+        Error::Io(e.to_string())
     }
 }
 
 pub trait Solver {
-    fn solve(&self, cnf: CNF) -> Result<Vec<Literal>, Error>;
-}
\ No newline at end of file
+    // Solves the CNF, answering Outcome::Unknown as soon as any of the limits is reached.
+    fn solve_with_limits(&self, cnf: CNF, limits: &Limits) -> Result<Outcome, Error>;
+
+    fn solve(&self, cnf: CNF) -> Result<Outcome, Error> {
+        self.solve_with_limits(cnf, &Limits::new())
+    }
+
+    // Counters of the last call as (name, value) pairs, empty if the solver keeps none.
+    fn statistics(&self) -> Vec<(&'static str, u64)> {
+        Vec::new()
+    }
+}
+
+// Solvers that can justify an UNSAT answer with a DRAT refutation.
+pub trait ProofSolver: Solver {
+    fn solve_with_proof(&self, cnf: CNF, proof: &mut dyn ProofTracer) -> Result<Outcome, Error>;
+}
+
+// Modeled on IPASIR: clauses are kept between calls together with everything
+// learnt from them, while assumptions only hold for a single call.
+pub trait IncrementalSolver {
+    fn add_clause(&mut self, clause: Vec<Literal>);
+    fn solve_with_assumptions(&mut self, assumptions: &[Literal]) -> Result<Outcome, Error>;
+
+    // Value of the literal in the last model, None if unassigned or the last call was not SAT.
+    fn value(&self, l: Literal) -> Option<bool>;
+
+    // Assumptions that were used to refute the formula in the last UNSAT call.
+    fn failed_assumptions(&self) -> Vec<Literal>;
+
+    // Limits applied to every following call. The counters start over with
+    // each call, while a deadline, set_timeout's included, is an instant
+    // shared by all of them.
+    fn set_limits(&mut self, limits: Limits);
+
+    fn add_cnf(&mut self, cnf: &CNF) {
+        for clause in cnf.clauses().iter() {
+            self.add_clause(clause.to_vec());
+        }
+    }
+
+    fn failed(&self, l: Literal) -> bool {
+        self.failed_assumptions().contains(&l)
+    }
+}
+
+// Lets any Solver stand in for an IncrementalSolver by solving the whole
+// formula again on every call. Nothing is learnt between calls, and all the
+// assumptions are reported as failed after an UNSAT call.
+pub struct Restarting<S: Solver> {
+    solver: S,
+    cnf: CNF,
+    limits: Limits,
+    model: Assignment,
+    failed: Vec<Literal>,
+}
+
+impl<S: Solver> Restarting<S> {
+    pub fn new(solver: S) -> Restarting<S> {
+        Restarting {
+            solver,
+            cnf: CNF::new(),
+            limits: Limits::new(),
+            model: Assignment::default(),
+            failed: Vec::new(),
+        }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.solver
+    }
+}
+
+impl<S: Solver> IncrementalSolver for Restarting<S> {
+    fn add_clause(&mut self, clause: Vec<Literal>) {
+        self.cnf.add_clause(clause);
+    }
+
+    fn solve_with_assumptions(&mut self, assumptions: &[Literal]) -> Result<Outcome, Error> {
+        self.model = Assignment::default();
+        self.failed.clear();
+
+        let mut cnf = self.cnf.clone();
+        for l in assumptions.iter() {
+            cnf.add_clause(vec![*l]);
+        }
+
+        let outcome = self.solver.solve_with_limits(cnf, &self.limits)?;
+        match &outcome {
+            Outcome::Sat(model) => self.model = model.clone(),
+            Outcome::Unsat => self.failed = assumptions.to_vec(),
+            Outcome::Unknown(_) => {},
+        }
+        Ok(outcome)
+    }
+
+    fn value(&self, l: Literal) -> Option<bool> {
+        self.model.value(l)
+    }
+
+    fn failed_assumptions(&self) -> Vec<Literal> {
+        self.failed.clone()
+    }
+
+    fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+}
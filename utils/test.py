@@ -160,11 +160,17 @@ def check_testcase(cnf, output, time_elapsed, testcase_name=''):
         print_fail_feedback("Solver output is empty", testcase_name)
         return False
     
-    if output[0] == 'E':
-        print_fail_feedback(output.rstrip(), testcase_name)
+    lines = output.splitlines()
+    status = [line for line in lines if line.startswith('s ')]
+
+    if len(status) == 0 or status[0] != 's SATISFIABLE':
+        print_fail_feedback(" ".join(status + [line for line in lines if line.startswith('c ') and not line.startswith('c stat ')]), testcase_name)
         return False
-    
-    values = list(map(int, output.split()))
+
+    values = []
+    for line in lines:
+        if line.startswith('v '):
+            values += [v for v in map(int, line.split()[1:]) if v != 0]
 
     eval_set = [0] * (cnf.size + 1)
 